@@ -0,0 +1,293 @@
+use std::fmt;
+
+use super::err::{self, Argument, Code, Error, Field, Severity};
+use super::source::Source;
+use super::{Line, Target, Warnings};
+
+// options of assemble
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    // name of the source code in diagnostics, included files are searched in its directory first
+    pub file_name: String,
+    pub include_dirs: Vec<String>,
+    pub two_pass: bool,
+    pub warnings: Warnings,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    Absolute,
+    Relative,
+    // imported by EXTREF
+    External,
+    Undefined,
+}
+
+#[derive(Clone, Debug)]
+pub struct Symbol {
+    // control section of the symbol
+    pub section: String,
+    pub name: String,
+    // None for external and undefined symbols
+    pub address: Option<u32>,
+    pub kind: SymbolKind,
+}
+
+// object code of a statement, or a literal placed by LTORG or END
+#[derive(Clone, Debug)]
+pub struct ObjectCode {
+    // index of the control section
+    pub section: usize,
+    pub address: u32,
+    pub bytes: Vec<u8>,
+    pub literal: Option<String>,
+}
+
+// a source code line after macro expansion and includes
+#[derive(Clone, Debug)]
+pub struct ListingLine {
+    pub file: String,
+    pub line: u32,
+    pub location: Option<u32>,
+    pub source: String,
+    pub codes: Vec<ObjectCode>,
+    // generated by a macro expansion
+    pub expanded: bool,
+    // in a branch of conditional assembly which isn't assembled
+    pub skipped: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Assembly {
+    // H to E records of all control sections
+    pub object_program: String,
+    pub symbols: Vec<Symbol>,
+    pub listing: Vec<ListingLine>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+// an error of a source code line, line 0 doesn't belong to a line
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub code: Code,
+    pub severity: Severity,
+    pub file: String,
+    pub line: u32,
+    pub source: String,
+    // byte range of the token in source which the error is about
+    pub span: Option<(usize, usize)>,
+    pub arguments: Vec<Argument>,
+    // rendered by the catalog of LANG
+    pub message: String,
+    // what the user may mean, such as the nearest mnemonic
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    // the statement number is the index of the line plus 1, 0 doesn't belong to a line,
+    // labeled is whether the first field of the statement is a label
+    pub(crate) fn new(lines: &[Line], statement: u32, error: &Error, labeled: bool) -> Diagnostic {
+        let (file, line, source) = match (statement as usize).checked_sub(1).and_then(|i| lines.get(i)) {
+            Some(line) => (line.file.clone(), line.line_number, line.source_code.clone()),
+            None => (String::new(), 0, String::new()),
+        };
+        let field = error.field.and_then(|field| field_range(&source, labeled, field));
+        Diagnostic {
+            code: error.code,
+            severity: error.code.severity(),
+            file,
+            line,
+            span: span(&source, field, &error.arguments),
+            source,
+            arguments: error.arguments.clone(),
+            message: error.to_string(),
+            suggestion: error.suggestion.clone(),
+        }
+    }
+
+    // file:line:col header, the source code line with the token underlined and help,
+    // severity, underline and help are colored by ANSI escape codes if color is true
+    pub fn render(&self, color: bool) -> String {
+        let paint = |text: &str, style: &str| match color {
+            true => format!("\x1b[{}m{}\x1b[0m", style, text),
+            false => text.to_string(),
+        };
+        let (severity, style) = match self.severity {
+            Severity::Error => ("error", "1;31"),
+            Severity::Warning => ("warning", "1;33"),
+        };
+        let help = match &self.suggestion {
+            Some(suggestion) => format!("{}: {}", paint("help", "1;36"), err::help(suggestion)),
+            None => String::new(),
+        };
+
+        if self.line == 0 {
+            let mut text = format!("{}: {}", paint(severity, style), self.message);
+            if help != "" {
+                text.push_str(&format!("\n= {}", help));
+            }
+            return text;
+        }
+
+        // the whole statement if the token isn't known
+        let source = self.source.trim_end();
+        let (start, end) = match self.span {
+            Some(span) => span,
+            None => (source.len() - source.trim_start().len(), source.len()),
+        };
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        // tabs are kept so the underline is below the token
+        let indent: String = source[..start].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        let underline = "^".repeat(source[start..end].chars().count().max(1));
+
+        let mut text = format!(
+            "{}:{}:{}: {}: {}\n",
+            self.file,
+            self.line,
+            source[..start].chars().count() + 1,
+            paint(severity, style),
+            self.message
+        );
+        text.push_str(&format!("{} {} {}\n", paint(&number, "1;34"), paint("|", "1;34"), source));
+        text.push_str(&format!("{} {} {}{}", gutter, paint("|", "1;34"), indent, paint(&underline, style)));
+        if help != "" {
+            text.push_str(&format!("\n{} {} {}", gutter, paint("=", "1;34"), help));
+        }
+        return text;
+    }
+}
+
+// byte range of a field in the source code, the operand is the rest of the statement
+// before the comment, None if the statement doesn't have the field
+fn field_range(source: &str, labeled: bool, field: Field) -> Option<(usize, usize)> {
+    // the first token from a byte offset
+    let token = |from: usize| -> (usize, usize) {
+        let start = source.len() - source[from..].trim_start().len();
+        let end = source[start..].find(char::is_whitespace).map_or(source.len(), |i| start + i);
+        return (start, end);
+    };
+    let label = if labeled { token(0) } else { (0, 0) };
+    let mnemonic = token(label.1);
+
+    let (start, end) = match field {
+        Field::Label if !labeled => return None,
+        Field::Label => label,
+        Field::Mnemonic => mnemonic,
+        Field::Operand => {
+            let (start, _) = token(mnemonic.1);
+            let end = source[start..].find('.').map_or(source.len(), |i| start + i);
+            (start, start + source[start..end].trim_end().len())
+        }
+    };
+    if start == end {
+        return None;
+    }
+    return Some((start, end));
+}
+
+// the first text argument found as a token in the field, ignoring case,
+// the whole field if no argument is found, or in the whole source code without the field
+fn span(source: &str, field: Option<(usize, usize)>, arguments: &[Argument]) -> Option<(usize, usize)> {
+    let (from, to) = field.unwrap_or((0, source.len()));
+    let source = source.to_ascii_uppercase();
+    let word = |c: Option<char>| c.map_or(false, |c| c.is_ascii_alphanumeric() || c == '_');
+
+    for argument in arguments {
+        if let Argument::Text(text) = argument {
+            let text = text.trim().to_ascii_uppercase();
+            if text == "" {
+                continue;
+            }
+            for (start, _) in source[from..to].match_indices(&text) {
+                let (start, end) = (from + start, from + start + text.len());
+                if !word(source[..start].chars().next_back()) && !word(source[end..].chars().next()) {
+                    return Some((start, end));
+                }
+            }
+        }
+    }
+    return field;
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.render(false));
+    }
+}
+
+// errors of a source code, in the order they are found
+#[derive(Clone, Debug)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.0.iter().map(|diagnostic| diagnostic.to_string()).collect();
+        return write!(f, "{}", lines.join("\n"));
+    }
+}
+
+// assemble source code without writing files or printing
+pub fn assemble(source: &str, options: &Options) -> Result<Assembly, Diagnostics> {
+    let file_name = match options.file_name.as_str() {
+        "" => "<source>",
+        name => name,
+    };
+    let lines = Source::new(&options.include_dirs).lines(file_name, source);
+    let mut target = Target::with_include_dirs(&options.include_dirs);
+    target.two_pass = options.two_pass;
+    target.warnings = options.warnings.clone();
+
+    let output = match target.assemble_lines(lines) {
+        Ok(output) => output,
+        Err(e) => return Err(Diagnostics(vec![Diagnostic::new(&[], 0, &e, false)])),
+    };
+    // without errors, the diagnostics are warnings
+    let diagnostics = output.diagnostics();
+    if output.have_error {
+        return Err(Diagnostics(diagnostics));
+    }
+
+    let symbols = output
+        .parser
+        .symbol_list()
+        .into_iter()
+        .map(|(section, name, address, kind)| Symbol {
+            section,
+            name,
+            address,
+            kind: match kind {
+                'A' => SymbolKind::Absolute,
+                'R' => SymbolKind::Relative,
+                'E' => SymbolKind::External,
+                _ => SymbolKind::Undefined,
+            },
+        })
+        .collect();
+
+    let mut listing: Vec<ListingLine> = output
+        .lines
+        .iter()
+        .map(|line| ListingLine {
+            file: line.file.clone(),
+            line: line.line_number,
+            location: line.location,
+            source: line.source_code.clone(),
+            codes: Vec::new(),
+            expanded: line.expanded,
+            skipped: line.skipped,
+        })
+        .collect();
+    for (statement, code) in output.listing.codes() {
+        if let Some(line) = (statement as usize).checked_sub(1).and_then(|i| listing.get_mut(i)) {
+            line.codes.push(code);
+        }
+    }
+
+    return Ok(Assembly {
+        object_program: output.object_program,
+        symbols,
+        listing,
+        warnings: diagnostics,
+    });
+}
@@ -0,0 +1,348 @@
+use std::io::{self, BufRead, Write};
+
+use super::err;
+use super::object;
+use super::simulator::{self, Machine, Simulator, PC};
+use super::{Line, Target};
+
+fn help_message(bin_path: &str) -> String {
+    let msg = format!("Usage: {} debug <code path>", bin_path);
+    let msg = format!("{}\n{}", msg, "use -I <dir> to search included files in dir");
+    let msg = format!("{}\n{}", msg, "use --progaddr, --memory, --steps, --device and --not-ready as run");
+    let msg = format!("{}\n{}", msg, "commands are read from stdin, use --device to give the program its own input");
+
+    return msg;
+}
+
+fn commands() -> String {
+    let msg = String::from("break <where>        stop when PC is at where, where is a symbol or a hexadecimal address");
+    let msg = format!("{}\n{}", msg, "watch <where> [n]    stop when n bytes at where change, default is 3");
+    let msg = format!("{}\n{}", msg, "delete <where>       remove the breakpoint or watchpoint");
+    let msg = format!("{}\n{}", msg, "info                 show breakpoints and watchpoints");
+    let msg = format!("{}\n{}", msg, "step [n]             execute n instructions, default is 1");
+    let msg = format!("{}\n{}", msg, "continue             execute until a breakpoint, a watchpoint or the end");
+    let msg = format!("{}\n{}", msg, "registers            show registers");
+    let msg = format!("{}\n{}", msg, "memory <where> [n]   show n bytes from where, default is 16");
+    let msg = format!("{}\n{}", msg, "list                 show the source code around PC");
+    let msg = format!("{}\n{}", msg, "symbols              show symbols and their addresses");
+    let msg = format!("{}\n{}", msg, "quit                 stop debugging");
+
+    return msg;
+}
+
+pub struct Debugger {
+    code_file_path: String,
+    include_dirs: Vec<String>,
+    simulator: Simulator,
+}
+
+impl Debugger {
+    // args[1] is debug
+    pub fn new(args: &[String]) -> Result<Debugger, String> {
+        let mut code_file_paths: Vec<String> = Vec::new();
+        let mut include_dirs: Vec<String> = Vec::new();
+        let mut simulator = Simulator::empty();
+
+        let mut i = 2;
+        while i < args.len() {
+            if args[i] == "-I" || simulator::OPTIONS.contains(&args[i].as_str()) {
+                if i + 1 >= args.len() {
+                    return Err(help_message(args[0].as_str()));
+                }
+                if args[i] == "-I" {
+                    include_dirs.push(args[i + 1].clone());
+                } else {
+                    simulator.set_option(&args[i], &args[i + 1])?;
+                }
+                i += 1;
+            } else if args[i].starts_with('-') {
+                return Err(help_message(args[0].as_str()));
+            } else {
+                code_file_paths.push(args[i].clone());
+            }
+            i += 1;
+        }
+
+        if code_file_paths.len() == 0 {
+            return Err(help_message(args[0].as_str()));
+        }
+        if code_file_paths.len() > 1 {
+            return Err(err::handler().e004("debug"));
+        }
+
+        Ok(Debugger {
+            code_file_path: code_file_paths.remove(0),
+            include_dirs,
+            simulator,
+        })
+    }
+
+    pub fn run(&self) -> Result<(), String> {
+        let target = Target::with_include_dirs(&self.include_dirs);
+        let assembly = target.assemble_source(&self.code_file_path)?;
+        if assembly.have_error {
+            for diagnostic in assembly.diagnostics() {
+                println!("{}", diagnostic.render(target.color));
+            }
+            return Err(err::handler().e608(&self.code_file_path));
+        }
+
+        let sections = object::parse(&self.code_file_path, &assembly.object_program)?;
+        let (machine, image) = self.simulator.load(&sections)?;
+
+        // load address of each control section
+        let csaddr: Vec<u32> = image
+            .map
+            .iter()
+            .filter(|(_, symbol, _, _)| symbol.is_none())
+            .map(|(_, _, address, _)| *address)
+            .collect();
+        let load_address = |section: usize, location: u32| csaddr[section] + location.wrapping_sub(sections[section].start);
+
+        let mut symbols: Vec<(String, u32)> = Vec::new();
+        for (section, symbol, address, kind) in assembly.parser.symbol_list() {
+            let index = sections.iter().position(|s| s.name == section);
+            match (address, kind, index) {
+                (Some(address), 'A', _) => symbols.push((symbol, address)),
+                (Some(address), 'R', Some(index)) => symbols.push((symbol, load_address(index, address))),
+                _ => {}
+            }
+        }
+        let statements: Vec<(u32, u32, u32)> = assembly
+            .listing
+            .locations()
+            .into_iter()
+            .map(|(section, location, byte, statement)| (load_address(section, location), byte, statement))
+            .collect();
+
+        let mut session = Session {
+            machine,
+            simulator: &self.simulator,
+            lines: assembly.lines,
+            symbols,
+            statements,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+        };
+        println!("{} is loaded at {:06X}, type help for commands", image.name, image.start);
+        session.show_line();
+
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        loop {
+            print!("(debug) ");
+            io::stdout().flush().unwrap_or(());
+            let mut command = String::new();
+            match input.read_line(&mut command) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let words: Vec<&str> = command.split_whitespace().collect();
+            if words.len() == 0 {
+                continue;
+            }
+            if words[0] == "quit" || words[0] == "q" {
+                break;
+            }
+            if let Err(e) = session.command(&words) {
+                println!("{}", e);
+            }
+        }
+
+        return session.machine.devices.flush();
+    }
+}
+
+// a program being debugged
+struct Session<'a> {
+    machine: Machine,
+    simulator: &'a Simulator,
+    lines: Vec<Line>,
+    // symbols and their load addresses
+    symbols: Vec<(String, u32)>,
+    // load address, length and statement number of each object code
+    statements: Vec<(u32, u32, u32)>,
+    breakpoints: Vec<u32>,
+    // address, length and the bytes when last checked
+    watchpoints: Vec<(u32, u32, Vec<u8>)>,
+}
+
+impl<'a> Session<'a> {
+    fn command(&mut self, words: &[&str]) -> Result<(), String> {
+        let argument = |i: usize| -> Result<&str, String> {
+            match words.get(i) {
+                Some(word) => return Ok(*word),
+                None => return Err(commands()),
+            }
+        };
+        // optional count, decimal
+        let count = |i: usize, default: u32| -> Result<u32, String> {
+            match words.get(i) {
+                Some(word) => match word.parse::<u32>() {
+                    Ok(count) if count > 0 => return Ok(count),
+                    _ => return Err(err::handler().e606(word, words[0])),
+                },
+                None => return Ok(default),
+            }
+        };
+
+        match words[0] {
+            "break" | "b" => {
+                let address = self.address(argument(1)?)?;
+                if !self.breakpoints.contains(&address) {
+                    self.breakpoints.push(address);
+                }
+                println!("Breakpoint at {}", self.name(address));
+            }
+            "watch" | "w" => {
+                let address = self.address(argument(1)?)?;
+                let length = count(2, 3)?;
+                let bytes = self.bytes(address, length)?;
+                self.watchpoints.retain(|(a, _, _)| *a != address);
+                self.watchpoints.push((address, length, bytes));
+                println!("Watchpoint at {}, {} bytes", self.name(address), length);
+            }
+            "delete" | "d" => {
+                let address = self.address(argument(1)?)?;
+                self.breakpoints.retain(|&a| a != address);
+                self.watchpoints.retain(|(a, _, _)| *a != address);
+            }
+            "info" | "i" => {
+                for &address in &self.breakpoints {
+                    println!("Breakpoint at {}", self.name(address));
+                }
+                for (address, length, _) in &self.watchpoints {
+                    println!("Watchpoint at {}, {} bytes", self.name(*address), length);
+                }
+            }
+            "step" | "s" => {
+                let count = count(1, 1)?;
+                for _ in 0..count {
+                    if !self.execute()? {
+                        break;
+                    }
+                }
+                self.show_line();
+            }
+            "continue" | "c" => {
+                while self.execute()? && !self.breakpoints.contains(&self.machine.registers[PC]) {}
+                if self.breakpoints.contains(&self.machine.registers[PC]) {
+                    println!("Breakpoint at {}", self.name(self.machine.registers[PC]));
+                }
+                self.show_line();
+            }
+            "registers" | "r" => println!("{}", simulator::registers(&self.machine)),
+            "memory" | "m" | "x" => {
+                let address = self.address(argument(1)?)?;
+                let bytes = self.bytes(address, count(2, 16)?)?;
+                for (n, chunk) in bytes.chunks(16).enumerate() {
+                    let mut row = format!("{:06X} ", address + (n * 16) as u32);
+                    for (i, byte) in chunk.iter().enumerate() {
+                        if i % 4 == 0 {
+                            row.push_str(" ");
+                        }
+                        row.push_str(&format!("{:02X}", byte));
+                    }
+                    println!("{}", row);
+                }
+            }
+            "list" | "l" => self.list(),
+            "symbols" => {
+                for (symbol, address) in &self.symbols {
+                    println!("{:<8} {:06X}", symbol, address);
+                }
+            }
+            _ => println!("{}", commands()),
+        }
+
+        return Ok(());
+    }
+
+    // execute an instruction, false if the program stops or a watchpoint changes
+    fn execute(&mut self) -> Result<bool, String> {
+        if self.machine.halted {
+            println!("\nHalted after {} steps", self.machine.steps);
+            return Ok(false);
+        }
+        self.simulator.step(&mut self.machine)?;
+        if self.machine.halted {
+            println!("\nHalted after {} steps", self.machine.steps);
+            return Ok(false);
+        }
+
+        let mut changed = false;
+        for i in 0..self.watchpoints.len() {
+            let (address, length, old) = self.watchpoints[i].clone();
+            let new = self.bytes(address, length)?;
+            if new != old {
+                let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02X}", b)).collect::<String>();
+                println!("Watchpoint at {}: {} -> {}", self.name(address), hex(&old), hex(&new));
+                self.watchpoints[i].2 = new;
+                changed = true;
+            }
+        }
+
+        return Ok(!changed);
+    }
+
+    // a symbol or a hexadecimal address
+    fn address(&self, text: &str) -> Result<u32, String> {
+        let symbol = text.to_uppercase();
+        if let Some((_, address)) = self.symbols.iter().find(|(name, _)| *name == symbol) {
+            return Ok(*address);
+        }
+        match u32::from_str_radix(text, 16) {
+            Ok(address) if (address as usize) < self.machine.memory.len() => return Ok(address),
+            _ => return Err(err::handler().e609(text)),
+        }
+    }
+
+    fn bytes(&self, address: u32, length: u32) -> Result<Vec<u8>, String> {
+        let mut bytes: Vec<u8> = Vec::new();
+        for i in 0..length {
+            bytes.push(self.machine.byte(address + i)?);
+        }
+        return Ok(bytes);
+    }
+
+    // address with the symbol at it
+    fn name(&self, address: u32) -> String {
+        match self.symbols.iter().find(|(_, a)| *a == address) {
+            Some((symbol, _)) => return format!("{:06X} {}", address, symbol),
+            None => return format!("{:06X}", address),
+        }
+    }
+
+    // index of the source code line of the object code at PC
+    fn line_index(&self) -> Option<usize> {
+        let pc = self.machine.registers[PC];
+        return self
+            .statements
+            .iter()
+            .find(|(address, byte, _)| *address <= pc && pc < address + byte.max(&1))
+            .map(|(_, _, statement)| (*statement - 1) as usize);
+    }
+
+    fn show_line(&self) {
+        let pc = self.machine.registers[PC];
+        match self.line_index().and_then(|i| self.lines.get(i)) {
+            Some(line) => println!("{:06X} {}:\t{}", pc, line.position(), line.source_code.trim()),
+            None => println!("{:06X} no source code", pc),
+        }
+    }
+
+    // 3 lines before and after the current line
+    fn list(&self) {
+        let index = match self.line_index() {
+            Some(index) => index,
+            None => return self.show_line(),
+        };
+        let start = index.saturating_sub(3);
+        let end = (index + 4).min(self.lines.len());
+        for i in start..end {
+            let marker = if i == index { "=>" } else { "  " };
+            let line = &self.lines[i];
+            println!("{} {}:\t{}", marker, line.position(), line.source_code);
+        }
+    }
+}
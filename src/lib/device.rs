@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+use super::err;
+
+// a device mapped to a file, - is stdin for RD and stdout for WD
+struct Device {
+    path: String,
+    // opened by the first RD or WD
+    input: Option<Box<dyn Read>>,
+    output: Option<Box<dyn Write>>,
+    // TD left to report not ready
+    busy: u32,
+}
+
+// devices of TD, RD and WD by device number, devices not mapped use stdin and stdout
+pub struct Devices {
+    devices: HashMap<u8, Device>,
+    // TD reports not ready this many times after each RD or WD
+    not_ready: u32,
+}
+
+impl Devices {
+    pub fn new(mappings: &[(u8, String)], not_ready: u32) -> Devices {
+        let mut devices: HashMap<u8, Device> = HashMap::new();
+        for (number, path) in mappings {
+            devices.insert(
+                *number,
+                Device {
+                    path: path.clone(),
+                    input: None,
+                    output: None,
+                    busy: not_ready,
+                },
+            );
+        }
+
+        Devices { devices, not_ready }
+    }
+
+    fn device(&mut self, number: u8) -> &mut Device {
+        let not_ready = self.not_ready;
+        return self.devices.entry(number).or_insert_with(|| Device {
+            path: String::from("-"),
+            input: None,
+            output: None,
+            busy: not_ready,
+        });
+    }
+
+    // the file of the device if it is opened in the other direction by any device,
+    // writing a file which is being read would empty it
+    fn shared(&self, number: u8, writing: bool) -> Option<String> {
+        let path = &self.devices.get(&number)?.path;
+        if path == "-" {
+            return None;
+        }
+        let opened = self.devices.values().any(|device| match writing {
+            true => device.path == *path && device.input.is_some(),
+            false => device.path == *path && device.output.is_some(),
+        });
+        return opened.then(|| path.clone());
+    }
+
+    // true if the device is ready
+    pub fn test(&mut self, number: u8) -> bool {
+        let device = self.device(number);
+        if device.busy > 0 {
+            device.busy -= 1;
+            return false;
+        }
+        return true;
+    }
+
+    // 0 at the end of the input
+    pub fn read(&mut self, number: u8) -> Result<u8, String> {
+        if let Some(path) = self.shared(number, false) {
+            return Err(err::handler().e610(number, &path));
+        }
+        let not_ready = self.not_ready;
+        let device = self.device(number);
+        device.busy = not_ready;
+        if device.input.is_none() {
+            let input: Box<dyn Read> = if device.path == "-" {
+                Box::new(io::stdin())
+            } else {
+                match File::open(&device.path) {
+                    Ok(file) => Box::new(BufReader::new(file)),
+                    Err(e) => return Err(err::handler().e607(number, &device.path, &e.to_string())),
+                }
+            };
+            device.input = Some(input);
+        }
+
+        let mut byte = [0u8];
+        match device.input.as_mut().unwrap().read(&mut byte) {
+            Ok(0) => return Ok(0),
+            Ok(_) => return Ok(byte[0]),
+            Err(e) => return Err(err::handler().e607(number, &device.path, &e.to_string())),
+        }
+    }
+
+    pub fn write(&mut self, number: u8, byte: u8) -> Result<(), String> {
+        if let Some(path) = self.shared(number, true) {
+            return Err(err::handler().e610(number, &path));
+        }
+        let not_ready = self.not_ready;
+        let device = self.device(number);
+        device.busy = not_ready;
+        if device.output.is_none() {
+            let output: Box<dyn Write> = if device.path == "-" {
+                Box::new(io::stdout())
+            } else {
+                match File::create(&device.path) {
+                    Ok(file) => Box::new(BufWriter::new(file)),
+                    Err(e) => return Err(err::handler().e607(number, &device.path, &e.to_string())),
+                }
+            };
+            device.output = Some(output);
+        }
+
+        let output = device.output.as_mut().unwrap();
+        if let Err(e) = output.write_all(&[byte]) {
+            return Err(err::handler().e607(number, &device.path, &e.to_string()));
+        }
+        // stdout is flushed for interactive programs, files when the program stops
+        if device.path == "-" {
+            output.flush().unwrap_or(());
+        }
+        return Ok(());
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        for (number, device) in self.devices.iter_mut() {
+            if let Some(output) = device.output.as_mut() {
+                if let Err(e) = output.flush() {
+                    return Err(err::handler().e607(*number, &device.path, &e.to_string()));
+                }
+            }
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,677 @@
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+
+use super::err;
+use super::object::{self, ControlSection};
+use super::parser::opcode_table::OpcodeTable;
+use super::source::Source;
+use super::Target;
+
+fn help_message(bin_path: &str) -> String {
+    let msg = format!("Usage: {} disasm <object path>", bin_path);
+    let msg = format!("{}\n{}", msg, "use -o <out file name> for output file name, default is a.asm");
+
+    return msg;
+}
+
+// register names of format 2 known by the assembler
+const REGISTERS: [&str; 7] = ["A", "X", "L", "B", "S", "T", "F"];
+
+// operand of format 3/4 or WORD, local is an address in the control section
+// written as its label, externals are symbols of R record with + or -
+struct Reference {
+    prefix: &'static str,
+    local: Option<u32>,
+    constant: i64,
+    externals: Vec<(char, String)>,
+    indexed: bool,
+}
+
+impl Reference {
+    fn constant(prefix: &'static str, constant: u32, indexed: bool) -> Reference {
+        Reference {
+            prefix,
+            local: None,
+            constant: constant as i64,
+            externals: Vec::new(),
+            indexed,
+        }
+    }
+
+    fn local(prefix: &'static str, address: u32, indexed: bool) -> Reference {
+        Reference {
+            prefix,
+            local: Some(address),
+            constant: 0,
+            externals: Vec::new(),
+            indexed,
+        }
+    }
+
+    // None if a local address has no label
+    fn text(&self, labels: &BTreeMap<u32, Vec<String>>) -> Option<String> {
+        let mut text = String::new();
+        if let Some(address) = self.local {
+            text.push_str(labels.get(&address)?.first()?);
+        }
+        for (sign, symbol) in &self.externals {
+            if text.len() > 0 || *sign == '-' {
+                text.push(*sign);
+            }
+            text.push_str(symbol);
+        }
+        if self.constant < 0 {
+            text.push_str(&format!("-{}", -self.constant));
+        } else if self.constant > 0 && text.len() > 0 {
+            text.push_str(&format!("+{}", self.constant));
+        } else if text.len() == 0 {
+            text.push_str(&self.constant.to_string());
+        }
+        if self.indexed {
+            text.push_str(",X");
+        }
+
+        return Some(format!("{}{}", self.prefix, text));
+    }
+}
+
+enum Operand {
+    // registers of format 2, empty for format 1 and RSUB
+    Text(String),
+    Reference(Reference),
+}
+
+// a decoded instruction, operand is None if the assembler can't write the same object code
+struct Instruction {
+    mnemonic: String,
+    length: u32,
+    operand: Option<Operand>,
+    // the next instruction can be executed
+    continues: bool,
+    // jump target in the control section
+    jump: Option<u32>,
+}
+
+// a statement of the disassembled source code
+enum Item {
+    Instruction(String, String),
+    Base(String),
+    Word(String),
+    Byte(Vec<u8>),
+    Reserve(u32),
+}
+
+// a control section being disassembled
+struct Program<'a> {
+    section: &'a ControlSection,
+    memory: Vec<Option<u8>>,
+    // M records by address, length in half-bytes and the symbol
+    modifications: HashMap<u32, Vec<(u32, Option<(char, String)>)>>,
+    opcode_table: &'a OpcodeTable,
+}
+
+impl<'a> Program<'a> {
+    fn new(section: &'a ControlSection, opcode_table: &'a OpcodeTable) -> Program<'a> {
+        let mut memory: Vec<Option<u8>> = vec![None; section.length as usize];
+        for (address, bytes) in &section.texts {
+            for (i, byte) in bytes.iter().enumerate() {
+                if let Some(memory) = memory.get_mut((address + i as u32).wrapping_sub(section.start) as usize) {
+                    *memory = Some(*byte);
+                }
+            }
+        }
+        let mut modifications: HashMap<u32, Vec<(u32, Option<(char, String)>)>> = HashMap::new();
+        for (address, half_bytes, symbol) in &section.modifications {
+            modifications.entry(*address).or_default().push((*half_bytes, symbol.clone()));
+        }
+
+        Program {
+            section,
+            memory,
+            modifications,
+            opcode_table,
+        }
+    }
+
+    fn end(&self) -> u32 {
+        return self.section.start + self.section.length;
+    }
+
+    // a label can be defined at the end by EQU *
+    fn contains(&self, address: u32) -> bool {
+        return self.section.start <= address && address <= self.end();
+    }
+
+    fn byte(&self, address: u32) -> Option<u8> {
+        return *self.memory.get(address.wrapping_sub(self.section.start) as usize)?;
+    }
+
+    // relative addresses are modified by the loader only if the program starts at 0
+    fn relocatable(&self) -> bool {
+        return self.section.start == 0;
+    }
+
+    fn decode(&self, address: u32, base: Option<u32>) -> Option<Instruction> {
+        let first = self.byte(address)?;
+        let (mnemonic, format) = self.opcode_table.find(first & 0xfc)?;
+        let mut instruction = Instruction {
+            mnemonic: mnemonic.to_string(),
+            length: format as u32,
+            operand: None,
+            continues: mnemonic != "J" && mnemonic != "RSUB",
+            jump: None,
+        };
+
+        if format == 1 || format == 2 {
+            if first & 0x3 != 0 {
+                return None;
+            }
+            if format == 1 {
+                instruction.operand = Some(Operand::Text(String::new()));
+                return Some(instruction);
+            }
+            let registers = self.byte(address + 1)?;
+            let (r1, r2) = ((registers >> 4) as usize, (registers & 0xf) as usize);
+            if r1 < REGISTERS.len() && r2 < REGISTERS.len() {
+                let text = if (mnemonic == "CLEAR" || mnemonic == "TIXR") && r2 == 0 {
+                    REGISTERS[r1].to_string()
+                } else {
+                    format!("{},{}", REGISTERS[r1], REGISTERS[r2])
+                };
+                instruction.operand = Some(Operand::Text(text));
+            }
+            return Some(instruction);
+        }
+
+        // SIC instructions can't be written
+        let ni = first & 0x3;
+        if ni == 0 {
+            return None;
+        }
+        let second = self.byte(address + 1)?;
+        let third = self.byte(address + 2)? as u32;
+        let (indexed, base_relative, pc_relative, extended) =
+            (second & 0x80 != 0, second & 0x40 != 0, second & 0x20 != 0, second & 0x10 != 0);
+        let prefix = match ni {
+            0b01 => "#",
+            0b10 => "@",
+            _ => "",
+        };
+
+        let reference = if extended {
+            let fourth = self.byte(address + 3)? as u32;
+            let field = ((second as u32 & 0xf) << 16) | (third << 8) | fourth;
+            instruction.mnemonic = format!("+{}", mnemonic);
+            instruction.length = 4;
+            if base_relative || pc_relative {
+                None
+            } else {
+                self.reference(address + 1, 5, field, 20, prefix, indexed)
+            }
+        } else {
+            let displacement = ((second as u32 & 0xf) << 8) | third;
+            instruction.length = 3;
+            let pc = address + 3;
+            match (base_relative, pc_relative) {
+                (false, false) => Some(Reference::constant(prefix, displacement, indexed)),
+                (false, true) => {
+                    let target = pc.wrapping_add(((displacement << 20) as i32 >> 20) as u32) & 0xfffff;
+                    match self.contains(target) {
+                        true => Some(Reference::local(prefix, target, indexed)),
+                        false => None,
+                    }
+                }
+                // the assembler uses base relative only if pc relative is out of range
+                (true, false) => match base {
+                    Some(base) => {
+                        let target = base + displacement;
+                        let distance = target as i64 - pc as i64;
+                        if (-2048..=2047).contains(&distance) || !self.contains(target) {
+                            None
+                        } else {
+                            Some(Reference::local(prefix, target, indexed))
+                        }
+                    }
+                    None => None,
+                },
+                _ => None,
+            }
+        };
+
+        if mnemonic == "RSUB" {
+            if !extended && ni == 0b11 && second == 0 && third == 0 {
+                instruction.operand = Some(Operand::Text(String::new()));
+            }
+            return Some(instruction);
+        }
+        if let Some(reference) = &reference {
+            let jump = ["J", "JEQ", "JGT", "JLT", "JSUB"].contains(&mnemonic);
+            if jump && ni == 0b11 && !indexed && reference.externals.len() == 0 {
+                instruction.jump = reference.local;
+            }
+        }
+        instruction.operand = reference.map(Operand::Reference);
+
+        return Some(instruction);
+    }
+
+    // operand of a format 4 address or a WORD modified by M records
+    fn reference(
+        &self,
+        address: u32,
+        half_bytes: u32,
+        field: u32,
+        bits: u32,
+        prefix: &'static str,
+        indexed: bool,
+    ) -> Option<Reference> {
+        let empty = Vec::new();
+        let modifications = self.modifications.get(&address).unwrap_or(&empty);
+        if modifications.iter().any(|(length, _)| *length != half_bytes) {
+            return None;
+        }
+        let locals = modifications.iter().filter(|(_, symbol)| symbol.is_none()).count();
+        let externals: Vec<(char, String)> = modifications.iter().filter_map(|(_, symbol)| symbol.clone()).collect();
+        let signed = ((field << (32 - bits)) as i32 >> (32 - bits)) as i64;
+
+        let mut reference = Reference::constant(prefix, field, indexed);
+        if externals.len() > 0 {
+            reference.constant = signed;
+            reference.externals = externals;
+        }
+        if locals > 1 {
+            return None;
+        }
+        if locals == 1 || (!self.relocatable() && prefix != "#" && reference.externals.len() == 0) {
+            // local M records are not written for absolute programs, relative operands are addresses
+            if self.contains(field) {
+                reference.local = Some(field);
+                reference.constant = 0;
+            } else if locals == 1 {
+                return None;
+            }
+        }
+
+        return Some(reference);
+    }
+
+    // addresses of instructions reached from the first instruction and format 4 addresses
+    fn code(&self) -> BTreeSet<u32> {
+        let mut pending: Vec<u32> = match self.section.execution {
+            Some(address) => vec![address],
+            None => vec![self.section.start],
+        };
+        for (address, modifications) in &self.modifications {
+            // a record at address 0 can't be the address field of an instruction
+            if modifications.iter().any(|(length, _)| *length == 5) {
+                if let Some(address) = address.checked_sub(1) {
+                    pending.push(address);
+                }
+            }
+        }
+
+        let mut code: BTreeSet<u32> = BTreeSet::new();
+        while let Some(address) = pending.pop() {
+            if code.contains(&address) || address < self.section.start || address >= self.end() {
+                continue;
+            }
+            if let Some(instruction) = self.decode(address, None) {
+                code.insert(address);
+                if let Some(jump) = instruction.jump {
+                    pending.push(jump);
+                }
+                if instruction.continues {
+                    pending.push(address + instruction.length);
+                }
+            }
+        }
+
+        return code;
+    }
+
+    // length bytes are loaded and no label or M record is inside, except M records at modified
+    fn fits(&self, address: u32, length: u32, modified: Option<u32>, labels: &BTreeMap<u32, Vec<String>>) -> bool {
+        for i in 0..length {
+            let at = address + i;
+            if self.byte(at).is_none() || (i > 0 && labels.contains_key(&at)) {
+                return false;
+            }
+            if self.modifications.contains_key(&at) && modified != Some(at) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    // label of each address used as operand, the control section name is defined at the start
+    fn labels(&self, code: &BTreeSet<u32>, references: &[String]) -> BTreeMap<u32, Vec<String>> {
+        let mut addresses: BTreeSet<u32> = BTreeSet::new();
+        if let Some(address) = self.section.execution {
+            addresses.insert(address);
+        }
+        let mut base: Option<u32> = None;
+        for &address in code {
+            let instruction = match self.decode(address, base) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+            if let Some(Operand::Reference(reference)) = &instruction.operand {
+                if let Some(local) = reference.local {
+                    addresses.insert(local);
+                    if instruction.mnemonic == "LDB" && reference.prefix == "#" {
+                        base = Some(local);
+                    }
+                }
+            }
+        }
+        for (address, modifications) in &self.modifications {
+            if modifications.iter().any(|(length, symbol)| *length == 6 && symbol.is_none()) {
+                if let Some(word) = self.word(*address) {
+                    addresses.insert(word);
+                }
+            }
+        }
+
+        let mut labels: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+        labels.insert(self.section.start, vec![self.section.name.clone()]);
+        for (symbol, address) in &self.section.definitions {
+            if self.contains(*address) {
+                labels.entry(*address).or_default().push(symbol.clone());
+            }
+        }
+        for address in addresses {
+            if self.contains(address) && !labels.contains_key(&address) {
+                let mut label = format!("L{:04X}", address);
+                while references.contains(&label) {
+                    label.push('0');
+                }
+                labels.insert(address, vec![label]);
+            }
+        }
+
+        return labels;
+    }
+
+    fn word(&self, address: u32) -> Option<u32> {
+        let mut word = 0;
+        for i in 0..3 {
+            word = (word << 8) | self.byte(address + i)? as u32;
+        }
+        return Some(word);
+    }
+
+    // statements from the start to the end of the control section
+    fn items(&self, code: &BTreeSet<u32>, labels: &BTreeMap<u32, Vec<String>>) -> Vec<(u32, Item)> {
+        let mut items: Vec<(u32, Item)> = Vec::new();
+        let mut base: Option<u32> = None;
+        let mut address = self.section.start;
+
+        while address < self.end() {
+            // RESB until a loaded byte or a label
+            if self.byte(address).is_none() {
+                let mut length = 1;
+                while address + length < self.end()
+                    && self.byte(address + length).is_none()
+                    && !labels.contains_key(&(address + length))
+                {
+                    length += 1;
+                }
+                items.push((address, Item::Reserve(length)));
+                address += length;
+                continue;
+            }
+
+            if code.contains(&address) {
+                if let Some(instruction) = self.decode(address, base) {
+                    let modified = if instruction.length == 4 { Some(address + 1) } else { None };
+                    let operand = match &instruction.operand {
+                        Some(Operand::Text(text)) => Some(text.clone()),
+                        Some(Operand::Reference(reference)) => reference.text(labels),
+                        None => None,
+                    };
+                    if let (Some(operand), true) = (operand, self.fits(address, instruction.length, modified, labels)) {
+                        items.push((address, Item::Instruction(instruction.mnemonic.clone(), operand)));
+                        // BASE follows LDB of an address like the source code of the assembler
+                        if let Some(Operand::Reference(reference)) = &instruction.operand {
+                            if let (Some(local), "LDB", "#") = (reference.local, instruction.mnemonic.as_str(), reference.prefix) {
+                                items.push((address, Item::Base(labels[&local][0].clone())));
+                                base = Some(local);
+                            }
+                        }
+                        address += instruction.length;
+                        continue;
+                    }
+                }
+            }
+
+            // WORD with M records
+            let words = self.modifications.get(&address).map_or(false, |m| m.iter().all(|(length, _)| *length == 6));
+            if words && self.fits(address, 3, Some(address), labels) {
+                let text = self
+                    .reference(address, 6, self.word(address).unwrap_or(0), 24, "", false)
+                    .and_then(|reference| reference.text(labels));
+                if let Some(text) = text {
+                    items.push((address, Item::Word(text)));
+                    address += 3;
+                    continue;
+                }
+            }
+
+            // BYTE of at most 8 bytes
+            let mut bytes: Vec<u8> = vec![self.byte(address).unwrap()];
+            while bytes.len() < 8 {
+                let next = address + bytes.len() as u32;
+                if next >= self.end()
+                    || labels.contains_key(&next)
+                    || code.contains(&next)
+                    || self.modifications.contains_key(&next)
+                {
+                    break;
+                }
+                match self.byte(next) {
+                    Some(byte) => bytes.push(byte),
+                    None => break,
+                }
+            }
+            address += bytes.len() as u32;
+            items.push((address - bytes.len() as u32, Item::Byte(bytes)));
+        }
+
+        return items;
+    }
+}
+
+// source code of a control section, the first one starts with START
+fn disassemble(section: &ControlSection, first: bool, opcode_table: &OpcodeTable) -> (String, Option<String>) {
+    let program = Program::new(section, opcode_table);
+    let code = program.code();
+    let labels = program.labels(&code, &section.references);
+    let items = program.items(&code, &labels);
+
+    let statement = |label: &str, mnemonic: &str, operand: &str| -> String {
+        return format!("{}\t{}\t{}", label, mnemonic, operand).trim_end().to_string() + "\n";
+    };
+    let mut contents = if first {
+        statement(&section.name, "START", &format!("{:X}", section.start))
+    } else {
+        statement(&section.name, "CSECT", "")
+    };
+    if section.definitions.len() > 0 {
+        let symbols: Vec<&str> = section.definitions.iter().map(|(symbol, _)| symbol.as_str()).collect();
+        contents.push_str(&statement("", "EXTDEF", &symbols.join(",")));
+    }
+    if section.references.len() > 0 {
+        contents.push_str(&statement("", "EXTREF", &section.references.join(",")));
+    }
+
+    // labels are defined by the first statement at their address, more labels by EQU *
+    let mut defined: BTreeSet<u32> = BTreeSet::new();
+    let mut label_of = |address: u32, contents: &mut String| -> String {
+        if !defined.insert(address) {
+            return String::new();
+        }
+        let mut names: Vec<&String> = match labels.get(&address) {
+            Some(names) => names.iter().filter(|name| **name != section.name || address != section.start).collect(),
+            None => Vec::new(),
+        };
+        let label = match names.pop() {
+            Some(label) => label.clone(),
+            None => String::new(),
+        };
+        for name in names {
+            contents.push_str(&statement(name, "EQU", "*"));
+        }
+        return label;
+    };
+    for (address, item) in &items {
+        let mut label = match item {
+            Item::Base(_) => String::new(),
+            _ => label_of(*address, &mut contents),
+        };
+        // the assembler needs a label for BYTE, WORD and RESB
+        if let (Item::Byte(_) | Item::Word(_) | Item::Reserve(_), true) = (item, label.len() == 0) {
+            label = format!("L{:04X}", address);
+            while section.references.contains(&label) {
+                label.push('0');
+            }
+        }
+        let line = match item {
+            Item::Instruction(mnemonic, operand) => statement(&label, mnemonic, operand),
+            Item::Base(operand) => statement("", "BASE", operand),
+            Item::Word(operand) => statement(&label, "WORD", operand),
+            Item::Byte(bytes) => {
+                let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                statement(&label, "BYTE", &format!("X'{}'", hex.concat()))
+            }
+            Item::Reserve(length) => statement(&label, "RESB", &length.to_string()),
+        };
+        contents.push_str(&line);
+    }
+    let label = label_of(program.end(), &mut contents);
+    if label.len() > 0 {
+        contents.push_str(&statement(&label, "EQU", "*"));
+    }
+
+    let execution = section
+        .execution
+        .and_then(|address| labels.get(&address))
+        .map(|names| names[0].clone());
+    return (contents, execution);
+}
+
+// object programs are the same if the same bytes are loaded with the same records
+fn same(a: &ControlSection, b: &ControlSection) -> bool {
+    let memory = |section: &ControlSection| -> BTreeMap<u32, u8> {
+        let mut memory: BTreeMap<u32, u8> = BTreeMap::new();
+        for (address, bytes) in &section.texts {
+            for (i, byte) in bytes.iter().enumerate() {
+                memory.insert(address + i as u32, *byte);
+            }
+        }
+        return memory;
+    };
+    let sorted = |mut list: Vec<String>| -> Vec<String> {
+        list.sort();
+        return list;
+    };
+    let mut definitions = (a.definitions.clone(), b.definitions.clone());
+    definitions.0.sort();
+    definitions.1.sort();
+    let mut modifications = (a.modifications.clone(), b.modifications.clone());
+    modifications.0.sort();
+    modifications.1.sort();
+
+    return a.name == b.name
+        && a.start == b.start
+        && a.length == b.length
+        && (a.execution.is_none() || a.execution == b.execution)
+        && definitions.0 == definitions.1
+        && sorted(a.references.clone()) == sorted(b.references.clone())
+        && modifications.0 == modifications.1
+        && memory(a) == memory(b);
+}
+
+pub struct Disassembler {
+    object_file_path: String,
+    output_file_path: String,
+}
+
+impl Disassembler {
+    // args[1] is disasm
+    pub fn new(args: &[String]) -> Result<Disassembler, String> {
+        let mut object_file_paths: Vec<String> = Vec::new();
+        let mut output_file_name: Option<String> = None;
+
+        let mut i = 2;
+        while i < args.len() {
+            if args[i] == "-o" {
+                if i + 1 >= args.len() {
+                    return Err(help_message(args[0].as_str()));
+                }
+                let re = Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
+                if !re.is_match(&args[i + 1]) {
+                    return Err(err::handler().e001());
+                }
+                output_file_name = Some(args[i + 1].clone());
+                i += 1;
+            } else if args[i].starts_with('-') {
+                return Err(help_message(args[0].as_str()));
+            } else {
+                object_file_paths.push(args[i].clone());
+            }
+            i += 1;
+        }
+
+        if object_file_paths.len() != 1 {
+            return Err(help_message(args[0].as_str()));
+        }
+
+        Ok(Disassembler {
+            object_file_path: object_file_paths.remove(0),
+            output_file_path: format!("{}.asm", output_file_name.unwrap_or(String::from("a"))),
+        })
+    }
+
+    pub fn run(&self) -> Result<(), String> {
+        let sections = match fs::read_to_string(&self.object_file_path) {
+            Ok(contents) => object::parse(&self.object_file_path, &contents)?,
+            Err(_) => return Err(err::handler().e002()),
+        };
+
+        let opcode_table = OpcodeTable::new();
+        let mut contents = format!(". disassembled from {}\n", self.object_file_path);
+        let mut execution: Option<String> = None;
+        for (i, section) in sections.iter().enumerate() {
+            let (source, first_instruction) = disassemble(section, i == 0, &opcode_table);
+            contents.push_str(&source);
+            if i == 0 {
+                execution = first_instruction;
+            }
+        }
+        contents.push_str(&format!("\tEND\t{}\n", execution.unwrap_or_default()).replace("\t\n", "\n"));
+
+        if let Err(e) = fs::write(&self.output_file_path, &contents) {
+            return Err(err::handler().e003(&e.to_string()));
+        }
+
+        // the source code is assembled again to check the object code
+        let lines = Source::new(&[]).lines(&self.output_file_path, &contents);
+        let assembly = Target::with_include_dirs(&[]).assemble_lines(lines)?;
+        let reassembled = match assembly.have_error {
+            true => Vec::new(),
+            false => object::parse(&self.output_file_path, &assembly.object_program)?,
+        };
+        for i in 0..sections.len() {
+            if reassembled.get(i).map_or(true, |section| !same(&sections[i], section)) {
+                return Err(err::handler().e506(&self.output_file_path, &sections[i].name));
+            }
+        }
+        if reassembled.len() != sections.len() {
+            return Err(err::handler().e506(&self.output_file_path, &self.object_file_path));
+        }
+
+        println!("{} -> {}", self.object_file_path, self.output_file_path);
+        return Ok(());
+    }
+}
@@ -1,48 +1,101 @@
 use std::env;
+use std::fmt;
 
 pub struct EN;
 pub struct ZH;
 
 pub trait ErrMsg {
-    fn e001(&self) -> String;
-    fn e002(&self) -> String;
-    fn e003(&self, msg: &str) -> String;
-    fn e101(&self, symbol: &str) -> String;
-    fn e102(&self, symbol: &str) -> String;
-    fn e103(&self, symbol: &str) -> String;
-    fn e104(&self, symbol: &str) -> String;
-    fn e105(&self, symbol: &str) -> String;
-    fn e201(&self, mnemonic: &str) -> String;
-    fn e202(&self) -> String;
-    fn e203(&self, location: &str) -> String;
-    fn e204(&self) -> String;
-    fn e205(&self) -> String;
-    fn e206(&self) -> String;
-    fn e207(&self) -> String;
-    fn e208(&self) -> String;
-    fn e209(&self) -> String;
-    fn e210(&self, mnemonic: &str) -> String;
-    fn e211(&self, mnemonic: &str) -> String;
-    fn e212(&self, register: &str) -> String;
-    fn e213(&self) -> String;
-    fn e214(&self) -> String;
-    fn e301(&self) -> String;
-    fn e302(&self) -> String;
-    fn e303(&self) -> String;
-    fn e304(&self) -> String;
-    fn e305(&self, program_name: &str) -> String;
-    fn e306(&self) -> String;
-    fn e307(&self) -> String;
-    fn e308(&self, base: &str) -> String;
-    fn e309(&self) -> String;
-    fn e310(&self) -> String;
-    fn e311(&self, operand: &str) -> String;
-    fn e312(&self, operand: &str, base: &str) -> String;
-    fn e313(&self) -> String;
-    fn e999(&self, msg: &str) -> String;
+    type Output;
+
+    fn e001(&self) -> Self::Output;
+    fn e002(&self) -> Self::Output;
+    fn e003(&self, msg: &str) -> Self::Output;
+    fn e004(&self, option: &str) -> Self::Output;
+    fn e005(&self, failed: usize, total: usize) -> Self::Output;
+    fn e006(&self, value: &str, option: &str) -> Self::Output;
+    fn e007(&self, path: &str) -> Self::Output;
+    fn e008(&self, path: &str) -> Self::Output;
+    fn e101(&self, symbol: &str) -> Self::Output;
+    fn e102(&self, symbol: &str) -> Self::Output;
+    fn e103(&self, symbol: &str) -> Self::Output;
+    fn e104(&self, symbol: &str) -> Self::Output;
+    fn e105(&self, symbol: &str) -> Self::Output;
+    fn e106(&self, symbol: &str, directive: &str) -> Self::Output;
+    fn e201(&self, mnemonic: &str) -> Self::Output;
+    fn e202(&self) -> Self::Output;
+    fn e203(&self, location: &str) -> Self::Output;
+    fn e204(&self) -> Self::Output;
+    fn e205(&self) -> Self::Output;
+    fn e206(&self) -> Self::Output;
+    fn e207(&self) -> Self::Output;
+    fn e208(&self) -> Self::Output;
+    fn e209(&self) -> Self::Output;
+    fn e210(&self, mnemonic: &str) -> Self::Output;
+    fn e211(&self, mnemonic: &str) -> Self::Output;
+    fn e212(&self, register: &str) -> Self::Output;
+    fn e213(&self) -> Self::Output;
+    fn e214(&self) -> Self::Output;
+    fn e215(&self, value: i64) -> Self::Output;
+    fn e216(&self, expression: &str) -> Self::Output;
+    fn e217(&self, expression: &str) -> Self::Output;
+    fn e218(&self, expression: &str) -> Self::Output;
+    fn e219(&self, literal: &str) -> Self::Output;
+    fn e220(&self) -> Self::Output;
+    fn e221(&self, expression: &str) -> Self::Output;
+    fn e222(&self, expression: &str) -> Self::Output;
+    fn e223(&self, symbol: &str, section: &str) -> Self::Output;
+    fn e224(&self, directive: &str) -> Self::Output;
+    fn e301(&self) -> Self::Output;
+    fn e304(&self) -> Self::Output;
+    fn e305(&self, program_name: &str) -> Self::Output;
+    fn e306(&self) -> Self::Output;
+    fn e307(&self) -> Self::Output;
+    fn e308(&self, base: &str) -> Self::Output;
+    fn e309(&self) -> Self::Output;
+    fn e310(&self) -> Self::Output;
+    fn e311(&self, operand: &str) -> Self::Output;
+    fn e312(&self, operand: &str, base: &str) -> Self::Output;
+    fn e313(&self) -> Self::Output;
+    fn e314(&self, location: u32, pass_one: u32) -> Self::Output;
+    fn e401(&self) -> Self::Output;
+    fn e402(&self) -> Self::Output;
+    fn e403(&self, name: &str) -> Self::Output;
+    fn e404(&self, name: &str, argument: &str) -> Self::Output;
+    fn e405(&self, name: &str) -> Self::Output;
+    fn e406(&self, parameter: &str) -> Self::Output;
+    fn e407(&self, label: &str) -> Self::Output;
+    fn e408(&self, directive: &str, pair: &str) -> Self::Output;
+    fn e409(&self, condition: &str) -> Self::Output;
+    fn e410(&self, condition: &str) -> Self::Output;
+    fn e411(&self, file: &str) -> Self::Output;
+    fn e412(&self, files: &str) -> Self::Output;
+    fn e501(&self, file: &str, record: &str) -> Self::Output;
+    fn e502(&self, symbol: &str) -> Self::Output;
+    fn e503(&self, symbol: &str, section: &str) -> Self::Output;
+    fn e504(&self, address: u32, section: &str) -> Self::Output;
+    fn e505(&self, address: &str) -> Self::Output;
+    fn e506(&self, path: &str, section: &str) -> Self::Output;
+    fn e601(&self, opcode: u8, address: u32) -> Self::Output;
+    fn e602(&self, address: u32) -> Self::Output;
+    fn e603(&self, address: u32) -> Self::Output;
+    fn e604(&self, steps: u64) -> Self::Output;
+    fn e605(&self, size: u32) -> Self::Output;
+    fn e606(&self, value: &str, option: &str) -> Self::Output;
+    fn e607(&self, device: u8, path: &str, msg: &str) -> Self::Output;
+    fn e608(&self, path: &str) -> Self::Output;
+    fn e609(&self, text: &str) -> Self::Output;
+    fn e610(&self, device: u8, path: &str) -> Self::Output;
+    fn e999(&self, msg: &str) -> Self::Output;
+    fn w001(&self, symbol: &str) -> Self::Output;
+    fn w002(&self, mnemonic: &str) -> Self::Output;
+    fn w003(&self, mnemonic: &str) -> Self::Output;
+    fn w004(&self, operand: &str) -> Self::Output;
+    fn w005(&self, base: &str) -> Self::Output;
 }
 
 impl ErrMsg for EN {
+    type Output = String;
+
     fn e001(&self) -> String {
         return format!("E[001]: Invalid file name");
     }
@@ -52,6 +105,21 @@ impl ErrMsg for EN {
     fn e003(&self, msg: &str) -> String {
         return format!("E[003]: Can not write file: {}", msg);
     }
+    fn e004(&self, option: &str) -> String {
+        return format!("E[004]: {} can only be used with one source file", option);
+    }
+    fn e005(&self, failed: usize, total: usize) -> String {
+        return format!("E[005]: {} of {} source files failed", failed, total);
+    }
+    fn e006(&self, value: &str, option: &str) -> String {
+        return format!("E[006]: {} is not a legal value of {}", value, option);
+    }
+    fn e007(&self, path: &str) -> String {
+        return format!("E[007]: {} is both an input file and the output file", path);
+    }
+    fn e008(&self, path: &str) -> String {
+        return format!("E[008]: {} is the output file of more than one source file", path);
+    }
     fn e101(&self, symbol: &str) -> String {
         return format!("E[101]: Illegal symbol: {}", symbol);
     }
@@ -67,6 +135,12 @@ impl ErrMsg for EN {
     fn e105(&self, symbol: &str) -> String {
         return format!("E[105]: symbol {} is a reserved word", symbol);
     }
+    fn e106(&self, symbol: &str, directive: &str) -> String {
+        return format!(
+            "E[106]: symbol {} must be defined before it is used in {}",
+            symbol, directive
+        );
+    }
     fn e201(&self, mnemonic: &str) -> String {
         return format!("E[201]: unknown mnemonic: {}", mnemonic);
     }
@@ -87,7 +161,7 @@ impl ErrMsg for EN {
         );
     }
     fn e205(&self) -> String {
-        return format!("E[205]: Illegal number: must be decimal, and the range is -2048 to 2047");
+        return format!("E[205]: Illegal number: the range of WORD is -8388608 to 16777215");
     }
     fn e206(&self) -> String {
         // cspell:disable
@@ -118,14 +192,56 @@ impl ErrMsg for EN {
     fn e214(&self) -> String {
         return format!("E[214]: Illegal operand format: format is [symbol] or [symbol, X]");
     }
-    fn e301(&self) -> String {
-        return format!("E[301]: code need to start with a legal START");
+    fn e215(&self, value: i64) -> String {
+        return format!(
+            "E[215]: Operand value {} is out of range for format 3, use format 4",
+            value
+        );
+    }
+    fn e216(&self, expression: &str) -> String {
+        return format!("E[216]: Illegal expression: {}", expression);
+    }
+    fn e217(&self, expression: &str) -> String {
+        return format!(
+            "E[217]: Illegal relative expression: {}, relative terms must be paired and can not be multiplied or divided",
+            expression
+        );
+    }
+    fn e218(&self, expression: &str) -> String {
+        return format!("E[218]: Division by zero in expression: {}", expression);
+    }
+    fn e219(&self, literal: &str) -> String {
+        return format!(
+            "E[219]: Illegal literal: {}, literal is =C'xxxx', =X'xxxx' or =[absolute value] and can not be immediate",
+            literal
+        );
     }
-    fn e302(&self) -> String {
-        return format!("E[302]: BASE must be after LDB");
+    fn e220(&self) -> String {
+        return format!("E[220]: ORG without operand needs a previous ORG with operand");
     }
-    fn e303(&self) -> String {
-        return format!("E[303]: The next instruction of LDB must be BASE");
+    fn e221(&self, expression: &str) -> String {
+        return format!(
+            "E[221]: Expression depends on the address of program blocks which is not known before END: {}",
+            expression
+        );
+    }
+    fn e222(&self, expression: &str) -> String {
+        return format!(
+            "E[222]: External reference can only be used in format 4 instruction or WORD: {}",
+            expression
+        );
+    }
+    fn e223(&self, symbol: &str, section: &str) -> String {
+        return format!(
+            "E[223]: EXTDEF symbol {} is not defined in control section {}",
+            symbol, section
+        );
+    }
+    fn e224(&self, directive: &str) -> String {
+        return format!("E[224]: directive {} needs a label", directive);
+    }
+    fn e301(&self) -> String {
+        return format!("E[301]: code need to start with a legal START");
     }
     fn e304(&self) -> String {
         return format!("E[304]: END must be the last instruction");
@@ -160,12 +276,116 @@ impl ErrMsg for EN {
     fn e313(&self) -> String {
         return format!("E[313]: Operand and label can not be same");
     }
+    fn e314(&self, location: u32, pass_one: u32) -> String {
+        return format!("E[314]: location {:04X} is not {:04X} of pass 1, an error of pass 1 moved the location counter", location, pass_one);
+    }
+    fn e401(&self) -> String {
+        return format!("E[401]: MACRO need a macro name as label");
+    }
+    fn e402(&self) -> String {
+        return format!("E[402]: MEND without MACRO");
+    }
+    fn e403(&self, name: &str) -> String {
+        return format!("E[403]: Macro {} need to end with MEND", name);
+    }
+    fn e404(&self, name: &str, argument: &str) -> String {
+        return format!("E[404]: Macro {} has no parameter for argument {}", name, argument);
+    }
+    fn e405(&self, name: &str) -> String {
+        return format!("E[405]: Macro {} is expanded too deeply, maybe recursive", name);
+    }
+    fn e406(&self, parameter: &str) -> String {
+        return format!("E[406]: {} is not a legal macro parameter", parameter);
+    }
+    fn e407(&self, label: &str) -> String {
+        return format!("E[407]: SET need a macro-time variable as label, not {}", label);
+    }
+    fn e408(&self, directive: &str, pair: &str) -> String {
+        return format!("E[408]: {} without {}", directive, pair);
+    }
+    fn e409(&self, condition: &str) -> String {
+        return format!("E[409]: Illegal condition: {}", condition);
+    }
+    fn e410(&self, condition: &str) -> String {
+        return format!("E[410]: WHILE {} loops too many times", condition);
+    }
+    fn e411(&self, file: &str) -> String {
+        return format!("E[411]: Can not find the included file {}", file);
+    }
+    fn e412(&self, files: &str) -> String {
+        return format!("E[412]: Files include each other: {}", files);
+    }
+    fn e501(&self, file: &str, record: &str) -> String {
+        return format!("E[501]: Illegal object record in {}: {}", file, record);
+    }
+    fn e502(&self, symbol: &str) -> String {
+        return format!("E[502]: External symbol {} is defined more than once", symbol);
+    }
+    fn e503(&self, symbol: &str, section: &str) -> String {
+        return format!("E[503]: External symbol {} used by {} is not defined", symbol, section);
+    }
+    fn e504(&self, address: u32, section: &str) -> String {
+        return format!("E[504]: Address {:06X} is out of control section {}", address, section);
+    }
+    fn e505(&self, address: &str) -> String {
+        return format!("E[505]: {} is not a legal hexadecimal address", address);
+    }
+    fn e506(&self, path: &str, section: &str) -> String {
+        return format!("E[506]: {} does not assemble to the same object code of {}", path, section);
+    }
+    fn e601(&self, opcode: u8, address: u32) -> String {
+        return format!("E[601]: Illegal instruction {:02X} at {:06X}", opcode, address);
+    }
+    fn e602(&self, address: u32) -> String {
+        return format!("E[602]: Address {:06X} is out of memory", address);
+    }
+    fn e603(&self, address: u32) -> String {
+        return format!("E[603]: Division by zero at {:06X}", address);
+    }
+    fn e604(&self, steps: u64) -> String {
+        return format!("E[604]: Program does not stop in {} steps", steps);
+    }
+    fn e605(&self, size: u32) -> String {
+        return format!("E[605]: Program does not fit in memory of size {:X}", size);
+    }
+    fn e606(&self, value: &str, option: &str) -> String {
+        return format!("E[606]: {} is not a legal value of {}", value, option);
+    }
+    fn e607(&self, device: u8, path: &str, msg: &str) -> String {
+        return format!("E[607]: Device {:02X} can not use {}, {}", device, path, msg);
+    }
+    fn e608(&self, path: &str) -> String {
+        return format!("E[608]: {} has errors, it can not be debugged", path);
+    }
+    fn e609(&self, text: &str) -> String {
+        return format!("E[609]: {} is not a symbol or an address", text);
+    }
+    fn e610(&self, device: u8, path: &str) -> String {
+        return format!("E[610]: Device {:02X} can not read and write {}, writing a file empties it", device, path);
+    }
     fn e999(&self, msg: &str) -> String {
         return format!("E[999]: Assembler have bug: {}, please report it", msg);
     }
+    fn w001(&self, symbol: &str) -> String {
+        return format!("W[001]: label {} is not used", symbol);
+    }
+    fn w002(&self, mnemonic: &str) -> String {
+        return format!("W[002]: {} can not be reached", mnemonic);
+    }
+    fn w003(&self, mnemonic: &str) -> String {
+        return format!("W[003]: {} reserves no memory", mnemonic);
+    }
+    fn w004(&self, operand: &str) -> String {
+        return format!("W[004]: format 3 can address {}, format 4 is not needed", operand);
+    }
+    fn w005(&self, base: &str) -> String {
+        return format!("W[005]: base {} is not needed", base);
+    }
 }
 
 impl ErrMsg for ZH {
+    type Output = String;
+
     fn e001(&self) -> String {
         return format!("E[001]: 不合法的檔案名稱");
     }
@@ -175,6 +395,21 @@ impl ErrMsg for ZH {
     fn e003(&self, msg: &str) -> String {
         return format!("E[003]: 無法寫入檔案：{}", msg);
     }
+    fn e004(&self, option: &str) -> String {
+        return format!("E[004]: {} 只能用於單一原始碼檔案", option);
+    }
+    fn e005(&self, failed: usize, total: usize) -> String {
+        return format!("E[005]: {} 個原始碼檔案中有 {} 個失敗", total, failed);
+    }
+    fn e006(&self, value: &str, option: &str) -> String {
+        return format!("E[006]: {} 不是 {} 的合法值", value, option);
+    }
+    fn e007(&self, path: &str) -> String {
+        return format!("E[007]: {} 同時是輸入檔案和輸出檔案", path);
+    }
+    fn e008(&self, path: &str) -> String {
+        return format!("E[008]: {} 是多個原始碼檔案的輸出檔案", path);
+    }
     fn e101(&self, symbol: &str) -> String {
         return format!("E[101]: 不合法的符號: {}", symbol);
     }
@@ -190,6 +425,9 @@ impl ErrMsg for ZH {
     fn e105(&self, symbol: &str) -> String {
         return format!("E[105]: 符號 {} 是一個保留字", symbol);
     }
+    fn e106(&self, symbol: &str, directive: &str) -> String {
+        return format!("E[106]: 符號 {} 必須在用於 {} 之前定義", symbol, directive);
+    }
     fn e201(&self, mnemonic: &str) -> String {
         return format!("E[201]: 未知的助憶碼: {}", mnemonic);
     }
@@ -206,7 +444,7 @@ impl ErrMsg for ZH {
         return format!("E[204]: 不合法的大小：必須是正整數，並且小於 1048576 (word 是 3 位元組)");
     }
     fn e205(&self) -> String {
-        return format!("E[205]: 不合法的數字：必須是整數，並且範圍是 -2048 到 2047");
+        return format!("E[205]: 不合法的數字：WORD 的範圍是 -8388608 到 16777215");
     }
     fn e206(&self) -> String {
         // cspell:disable
@@ -237,14 +475,47 @@ impl ErrMsg for ZH {
     fn e214(&self) -> String {
         return format!("E[214]: 不合法的操作元格式：格式是 [符號] 或 [符號, X]");
     }
-    fn e301(&self) -> String {
-        return format!("E[301]: 程式需要以合法的 START 助憶碼開始");
+    fn e215(&self, value: i64) -> String {
+        return format!("E[215]: 操作元的值 {} 超出格式 3 的範圍，請使用格式 4", value);
+    }
+    fn e216(&self, expression: &str) -> String {
+        return format!("E[216]: 不合法的運算式: {}", expression);
+    }
+    fn e217(&self, expression: &str) -> String {
+        return format!(
+            "E[217]: 不合法的相對運算式: {}，相對項必須成對出現，並且不能做乘除",
+            expression
+        );
+    }
+    fn e218(&self, expression: &str) -> String {
+        return format!("E[218]: 運算式 {} 除以零", expression);
     }
-    fn e302(&self) -> String {
-        return format!("E[302]: BASE 必須在 LDB 之後");
+    fn e219(&self, literal: &str) -> String {
+        return format!(
+            "E[219]: 不合法的常值: {}，常值是 =C'xxxx'、=X'xxxx' 或 =[絕對值]，並且不能是立即定址",
+            literal
+        );
     }
-    fn e303(&self) -> String {
-        return format!("E[303]: LDB 之後的下一個指令必須是 BASE");
+    fn e220(&self) -> String {
+        return format!("E[220]: 沒有操作元的 ORG 之前必須有帶操作元的 ORG");
+    }
+    fn e221(&self, expression: &str) -> String {
+        return format!(
+            "E[221]: 運算式依賴程式區塊的位址，在 END 之前無法得知: {}",
+            expression
+        );
+    }
+    fn e222(&self, expression: &str) -> String {
+        return format!("E[222]: 外部參考只能用於格式 4 指令或 WORD: {}", expression);
+    }
+    fn e223(&self, symbol: &str, section: &str) -> String {
+        return format!("E[223]: EXTDEF 符號 {} 沒有在控制區段 {} 中定義", symbol, section);
+    }
+    fn e224(&self, directive: &str) -> String {
+        return format!("E[224]: 指令 {} 需要標籤", directive);
+    }
+    fn e301(&self) -> String {
+        return format!("E[301]: 程式需要以合法的 START 助憶碼開始");
     }
     fn e304(&self) -> String {
         return format!("E[304]: END 必須是最後一個指令");
@@ -279,9 +550,653 @@ impl ErrMsg for ZH {
     fn e313(&self) -> String {
         return format!("E[313]: 操作元跟符號不能一樣");
     }
+    fn e314(&self, location: u32, pass_one: u32) -> String {
+        return format!("E[314]: 位置 {:04X} 與第一階段的 {:04X} 不同，第一階段的錯誤移動了位置計數器", location, pass_one);
+    }
+    fn e401(&self) -> String {
+        return format!("E[401]: MACRO 需要以巨集名稱作為標籤");
+    }
+    fn e402(&self) -> String {
+        return format!("E[402]: MEND 沒有對應的 MACRO");
+    }
+    fn e403(&self, name: &str) -> String {
+        return format!("E[403]: 巨集 {} 需要以 MEND 結束", name);
+    }
+    fn e404(&self, name: &str, argument: &str) -> String {
+        return format!("E[404]: 巨集 {} 沒有對應引數 {} 的參數", name, argument);
+    }
+    fn e405(&self, name: &str) -> String {
+        return format!("E[405]: 巨集 {} 展開層數過多，可能是遞迴呼叫", name);
+    }
+    fn e406(&self, parameter: &str) -> String {
+        return format!("E[406]: {} 不是合法的巨集參數", parameter);
+    }
+    fn e407(&self, label: &str) -> String {
+        return format!("E[407]: SET 需要以巨集時期變數作為標籤，而不是 {}", label);
+    }
+    fn e408(&self, directive: &str, pair: &str) -> String {
+        return format!("E[408]: {} 沒有對應的 {}", directive, pair);
+    }
+    fn e409(&self, condition: &str) -> String {
+        return format!("E[409]: 不合法的條件: {}", condition);
+    }
+    fn e410(&self, condition: &str) -> String {
+        return format!("E[410]: WHILE {} 重複次數過多", condition);
+    }
+    fn e411(&self, file: &str) -> String {
+        return format!("E[411]: 找不到引入的檔案 {}", file);
+    }
+    fn e412(&self, files: &str) -> String {
+        return format!("E[412]: 檔案互相引入: {}", files);
+    }
+    fn e501(&self, file: &str, record: &str) -> String {
+        return format!("E[501]: {} 中有不合法的目的碼紀錄: {}", file, record);
+    }
+    fn e502(&self, symbol: &str) -> String {
+        return format!("E[502]: 外部符號 {} 重複定義", symbol);
+    }
+    fn e503(&self, symbol: &str, section: &str) -> String {
+        return format!("E[503]: {} 使用的外部符號 {} 沒有定義", section, symbol);
+    }
+    fn e504(&self, address: u32, section: &str) -> String {
+        return format!("E[504]: 位址 {:06X} 超出控制段 {} 的範圍", address, section);
+    }
+    fn e505(&self, address: &str) -> String {
+        return format!("E[505]: {} 不是合法的十六進位位址", address);
+    }
+    fn e506(&self, path: &str, section: &str) -> String {
+        return format!("E[506]: {} 組譯後與 {} 的目的碼不同", path, section);
+    }
+    fn e601(&self, opcode: u8, address: u32) -> String {
+        return format!("E[601]: 位址 {:06X} 的指令 {:02X} 不合法", address, opcode);
+    }
+    fn e602(&self, address: u32) -> String {
+        return format!("E[602]: 位址 {:06X} 超出記憶體範圍", address);
+    }
+    fn e603(&self, address: u32) -> String {
+        return format!("E[603]: 位址 {:06X} 除以零", address);
+    }
+    fn e604(&self, steps: u64) -> String {
+        return format!("E[604]: 程式在 {} 步內沒有結束", steps);
+    }
+    fn e605(&self, size: u32) -> String {
+        return format!("E[605]: 程式超出大小為 {:X} 的記憶體", size);
+    }
+    fn e606(&self, value: &str, option: &str) -> String {
+        return format!("E[606]: {} 不是 {} 的合法值", value, option);
+    }
+    fn e607(&self, device: u8, path: &str, msg: &str) -> String {
+        return format!("E[607]: 裝置 {:02X} 無法使用 {}，{}", device, path, msg);
+    }
+    fn e608(&self, path: &str) -> String {
+        return format!("E[608]: {} 有錯誤，無法除錯", path);
+    }
+    fn e609(&self, text: &str) -> String {
+        return format!("E[609]: {} 不是符號或位址", text);
+    }
+    fn e610(&self, device: u8, path: &str) -> String {
+        return format!("E[610]: 裝置 {:02X} 不能同時讀取和寫入 {}，寫入檔案會清空它", device, path);
+    }
     fn e999(&self, msg: &str) -> String {
         return format!("E[999]: {}, 請幫忙回報", msg);
     }
+    fn w001(&self, symbol: &str) -> String {
+        return format!("W[001]: 標籤 {} 沒有被使用", symbol);
+    }
+    fn w002(&self, mnemonic: &str) -> String {
+        return format!("W[002]: {} 無法被執行到", mnemonic);
+    }
+    fn w003(&self, mnemonic: &str) -> String {
+        return format!("W[003]: {} 沒有保留任何記憶體", mnemonic);
+    }
+    fn w004(&self, operand: &str) -> String {
+        return format!("W[004]: 格式 3 可以定址 {}，不需要格式 4", operand);
+    }
+    fn w005(&self, base: &str) -> String {
+        return format!("W[005]: 不需要基底 {}", base);
+    }
+}
+
+// code of a message, the number in E[...] or W[...]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Code {
+    E001,
+    E002,
+    E003,
+    E004,
+    E005,
+    E006,
+    E007,
+    E008,
+    E101,
+    E102,
+    E103,
+    E104,
+    E105,
+    E106,
+    E201,
+    E202,
+    E203,
+    E204,
+    E205,
+    E206,
+    E207,
+    E208,
+    E209,
+    E210,
+    E211,
+    E212,
+    E213,
+    E214,
+    E215,
+    E216,
+    E217,
+    E218,
+    E219,
+    E220,
+    E221,
+    E222,
+    E223,
+    E224,
+    E301,
+    E304,
+    E305,
+    E306,
+    E307,
+    E308,
+    E309,
+    E310,
+    E311,
+    E312,
+    E313,
+    E314,
+    E401,
+    E402,
+    E403,
+    E404,
+    E405,
+    E406,
+    E407,
+    E408,
+    E409,
+    E410,
+    E411,
+    E412,
+    E501,
+    E502,
+    E503,
+    E504,
+    E505,
+    E506,
+    E601,
+    E602,
+    E603,
+    E604,
+    E605,
+    E606,
+    E607,
+    E608,
+    E609,
+    E610,
+    E999,
+    W001,
+    W002,
+    W003,
+    W004,
+    W005,
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{:?}", self);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Code {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Code::W001 | Code::W002 | Code::W003 | Code::W004 | Code::W005 => return Severity::Warning,
+            _ => return Severity::Error,
+        }
+    }
+}
+
+// an argument of a message, in the order of the parameters of ErrMsg
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Argument {
+    Text(String),
+    Number(i64),
+}
+
+impl From<&str> for Argument {
+    fn from(text: &str) -> Argument {
+        return Argument::Text(text.to_string());
+    }
+}
+
+// field of a statement which an error is about
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Label,
+    Mnemonic,
+    Operand,
+}
+
+// an error with its code and arguments, the message is rendered by a catalog
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub code: Code,
+    pub arguments: Vec<Argument>,
+    // what the user may mean, shown as help
+    pub suggestion: Option<String>,
+    // None if the error is about the whole statement or isn't raised by the parser
+    pub field: Option<Field>,
+}
+
+impl Error {
+    pub fn new(code: Code, arguments: Vec<Argument>) -> Error {
+        Error {
+            code,
+            arguments,
+            suggestion: None,
+            field: None,
+        }
+    }
+
+    pub fn suggest(mut self, suggestion: Option<String>) -> Error {
+        self.suggestion = suggestion;
+        return self;
+    }
+
+    // the field where the error is raised, a caller doesn't change it
+    pub fn at(mut self, field: Field) -> Error {
+        if self.field.is_none() {
+            self.field = Some(field);
+        }
+        return self;
+    }
+
+    fn text(&self, i: usize) -> &str {
+        match self.arguments.get(i) {
+            Some(Argument::Text(text)) => return text,
+            _ => return "",
+        }
+    }
+
+    fn number(&self, i: usize) -> i64 {
+        match self.arguments.get(i) {
+            Some(Argument::Number(number)) => return *number,
+            _ => return 0,
+        }
+    }
+
+    pub fn render(&self, handler: &dyn ErrMsg<Output = String>) -> String {
+        match self.code {
+            Code::E001 => handler.e001(),
+            Code::E002 => handler.e002(),
+            Code::E003 => handler.e003(self.text(0)),
+            Code::E004 => handler.e004(self.text(0)),
+            Code::E005 => handler.e005(self.number(0) as usize, self.number(1) as usize),
+            Code::E006 => handler.e006(self.text(0), self.text(1)),
+            Code::E007 => handler.e007(self.text(0)),
+            Code::E008 => handler.e008(self.text(0)),
+            Code::E101 => handler.e101(self.text(0)),
+            Code::E102 => handler.e102(self.text(0)),
+            Code::E103 => handler.e103(self.text(0)),
+            Code::E104 => handler.e104(self.text(0)),
+            Code::E105 => handler.e105(self.text(0)),
+            Code::E106 => handler.e106(self.text(0), self.text(1)),
+            Code::E201 => handler.e201(self.text(0)),
+            Code::E202 => handler.e202(),
+            Code::E203 => handler.e203(self.text(0)),
+            Code::E204 => handler.e204(),
+            Code::E205 => handler.e205(),
+            Code::E206 => handler.e206(),
+            Code::E207 => handler.e207(),
+            Code::E208 => handler.e208(),
+            Code::E209 => handler.e209(),
+            Code::E210 => handler.e210(self.text(0)),
+            Code::E211 => handler.e211(self.text(0)),
+            Code::E212 => handler.e212(self.text(0)),
+            Code::E213 => handler.e213(),
+            Code::E214 => handler.e214(),
+            Code::E215 => handler.e215(self.number(0)),
+            Code::E216 => handler.e216(self.text(0)),
+            Code::E217 => handler.e217(self.text(0)),
+            Code::E218 => handler.e218(self.text(0)),
+            Code::E219 => handler.e219(self.text(0)),
+            Code::E220 => handler.e220(),
+            Code::E221 => handler.e221(self.text(0)),
+            Code::E222 => handler.e222(self.text(0)),
+            Code::E223 => handler.e223(self.text(0), self.text(1)),
+            Code::E224 => handler.e224(self.text(0)),
+            Code::E301 => handler.e301(),
+            Code::E304 => handler.e304(),
+            Code::E305 => handler.e305(self.text(0)),
+            Code::E306 => handler.e306(),
+            Code::E307 => handler.e307(),
+            Code::E308 => handler.e308(self.text(0)),
+            Code::E309 => handler.e309(),
+            Code::E310 => handler.e310(),
+            Code::E311 => handler.e311(self.text(0)),
+            Code::E312 => handler.e312(self.text(0), self.text(1)),
+            Code::E313 => handler.e313(),
+            Code::E314 => handler.e314(self.number(0) as u32, self.number(1) as u32),
+            Code::E401 => handler.e401(),
+            Code::E402 => handler.e402(),
+            Code::E403 => handler.e403(self.text(0)),
+            Code::E404 => handler.e404(self.text(0), self.text(1)),
+            Code::E405 => handler.e405(self.text(0)),
+            Code::E406 => handler.e406(self.text(0)),
+            Code::E407 => handler.e407(self.text(0)),
+            Code::E408 => handler.e408(self.text(0), self.text(1)),
+            Code::E409 => handler.e409(self.text(0)),
+            Code::E410 => handler.e410(self.text(0)),
+            Code::E411 => handler.e411(self.text(0)),
+            Code::E412 => handler.e412(self.text(0)),
+            Code::E501 => handler.e501(self.text(0), self.text(1)),
+            Code::E502 => handler.e502(self.text(0)),
+            Code::E503 => handler.e503(self.text(0), self.text(1)),
+            Code::E504 => handler.e504(self.number(0) as u32, self.text(1)),
+            Code::E505 => handler.e505(self.text(0)),
+            Code::E506 => handler.e506(self.text(0), self.text(1)),
+            Code::E601 => handler.e601(self.number(0) as u8, self.number(1) as u32),
+            Code::E602 => handler.e602(self.number(0) as u32),
+            Code::E603 => handler.e603(self.number(0) as u32),
+            Code::E604 => handler.e604(self.number(0) as u64),
+            Code::E605 => handler.e605(self.number(0) as u32),
+            Code::E606 => handler.e606(self.text(0), self.text(1)),
+            Code::E607 => handler.e607(self.number(0) as u8, self.text(1), self.text(2)),
+            Code::E608 => handler.e608(self.text(0)),
+            Code::E609 => handler.e609(self.text(0)),
+            Code::E610 => handler.e610(self.number(0) as u8, self.text(1)),
+            Code::E999 => handler.e999(self.text(0)),
+            Code::W001 => handler.w001(self.text(0)),
+            Code::W002 => handler.w002(self.text(0)),
+            Code::W003 => handler.w003(self.text(0)),
+            Code::W004 => handler.w004(self.text(0)),
+            Code::W005 => handler.w005(self.text(0)),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.render(handler().as_ref()));
+    }
+}
+
+// errors are rendered when they reach a caller which reports text
+impl From<Error> for String {
+    fn from(error: Error) -> String {
+        return error.to_string();
+    }
+}
+
+// records the code and the arguments instead of a message
+pub struct Recorder;
+
+impl ErrMsg for Recorder {
+    type Output = Error;
+
+    fn e001(&self) -> Error {
+        return Error::new(Code::E001, vec![]);
+    }
+    fn e002(&self) -> Error {
+        return Error::new(Code::E002, vec![]);
+    }
+    fn e003(&self, msg: &str) -> Error {
+        return Error::new(Code::E003, vec![Argument::from(msg)]);
+    }
+    fn e004(&self, option: &str) -> Error {
+        return Error::new(Code::E004, vec![Argument::from(option)]);
+    }
+    fn e005(&self, failed: usize, total: usize) -> Error {
+        return Error::new(Code::E005, vec![Argument::Number(failed as i64), Argument::Number(total as i64)]);
+    }
+    fn e006(&self, value: &str, option: &str) -> Error {
+        return Error::new(Code::E006, vec![Argument::from(value), Argument::from(option)]);
+    }
+    fn e007(&self, path: &str) -> Error {
+        return Error::new(Code::E007, vec![Argument::from(path)]);
+    }
+    fn e008(&self, path: &str) -> Error {
+        return Error::new(Code::E008, vec![Argument::from(path)]);
+    }
+    fn e101(&self, symbol: &str) -> Error {
+        return Error::new(Code::E101, vec![Argument::from(symbol)]);
+    }
+    fn e102(&self, symbol: &str) -> Error {
+        return Error::new(Code::E102, vec![Argument::from(symbol)]);
+    }
+    fn e103(&self, symbol: &str) -> Error {
+        return Error::new(Code::E103, vec![Argument::from(symbol)]);
+    }
+    fn e104(&self, symbol: &str) -> Error {
+        return Error::new(Code::E104, vec![Argument::from(symbol)]);
+    }
+    fn e105(&self, symbol: &str) -> Error {
+        return Error::new(Code::E105, vec![Argument::from(symbol)]);
+    }
+    fn e106(&self, symbol: &str, directive: &str) -> Error {
+        return Error::new(Code::E106, vec![Argument::from(symbol), Argument::from(directive)]);
+    }
+    fn e201(&self, mnemonic: &str) -> Error {
+        return Error::new(Code::E201, vec![Argument::from(mnemonic)]);
+    }
+    fn e202(&self) -> Error {
+        return Error::new(Code::E202, vec![]);
+    }
+    fn e203(&self, location: &str) -> Error {
+        return Error::new(Code::E203, vec![Argument::from(location)]);
+    }
+    fn e204(&self) -> Error {
+        return Error::new(Code::E204, vec![]);
+    }
+    fn e205(&self) -> Error {
+        return Error::new(Code::E205, vec![]);
+    }
+    fn e206(&self) -> Error {
+        return Error::new(Code::E206, vec![]);
+    }
+    fn e207(&self) -> Error {
+        return Error::new(Code::E207, vec![]);
+    }
+    fn e208(&self) -> Error {
+        return Error::new(Code::E208, vec![]);
+    }
+    fn e209(&self) -> Error {
+        return Error::new(Code::E209, vec![]);
+    }
+    fn e210(&self, mnemonic: &str) -> Error {
+        return Error::new(Code::E210, vec![Argument::from(mnemonic)]);
+    }
+    fn e211(&self, mnemonic: &str) -> Error {
+        return Error::new(Code::E211, vec![Argument::from(mnemonic)]);
+    }
+    fn e212(&self, register: &str) -> Error {
+        return Error::new(Code::E212, vec![Argument::from(register)]);
+    }
+    fn e213(&self) -> Error {
+        return Error::new(Code::E213, vec![]);
+    }
+    fn e214(&self) -> Error {
+        return Error::new(Code::E214, vec![]);
+    }
+    fn e215(&self, value: i64) -> Error {
+        return Error::new(Code::E215, vec![Argument::Number(value)]);
+    }
+    fn e216(&self, expression: &str) -> Error {
+        return Error::new(Code::E216, vec![Argument::from(expression)]);
+    }
+    fn e217(&self, expression: &str) -> Error {
+        return Error::new(Code::E217, vec![Argument::from(expression)]);
+    }
+    fn e218(&self, expression: &str) -> Error {
+        return Error::new(Code::E218, vec![Argument::from(expression)]);
+    }
+    fn e219(&self, literal: &str) -> Error {
+        return Error::new(Code::E219, vec![Argument::from(literal)]);
+    }
+    fn e220(&self) -> Error {
+        return Error::new(Code::E220, vec![]);
+    }
+    fn e221(&self, expression: &str) -> Error {
+        return Error::new(Code::E221, vec![Argument::from(expression)]);
+    }
+    fn e222(&self, expression: &str) -> Error {
+        return Error::new(Code::E222, vec![Argument::from(expression)]);
+    }
+    fn e223(&self, symbol: &str, section: &str) -> Error {
+        return Error::new(Code::E223, vec![Argument::from(symbol), Argument::from(section)]);
+    }
+    fn e224(&self, directive: &str) -> Error {
+        return Error::new(Code::E224, vec![Argument::from(directive)]);
+    }
+    fn e301(&self) -> Error {
+        return Error::new(Code::E301, vec![]);
+    }
+    fn e304(&self) -> Error {
+        return Error::new(Code::E304, vec![]);
+    }
+    fn e305(&self, program_name: &str) -> Error {
+        return Error::new(Code::E305, vec![Argument::from(program_name)]);
+    }
+    fn e306(&self) -> Error {
+        return Error::new(Code::E306, vec![]);
+    }
+    fn e307(&self) -> Error {
+        return Error::new(Code::E307, vec![]);
+    }
+    fn e308(&self, base: &str) -> Error {
+        return Error::new(Code::E308, vec![Argument::from(base)]);
+    }
+    fn e309(&self) -> Error {
+        return Error::new(Code::E309, vec![]);
+    }
+    fn e310(&self) -> Error {
+        return Error::new(Code::E310, vec![]);
+    }
+    fn e311(&self, operand: &str) -> Error {
+        return Error::new(Code::E311, vec![Argument::from(operand)]);
+    }
+    fn e312(&self, operand: &str, base: &str) -> Error {
+        return Error::new(Code::E312, vec![Argument::from(operand), Argument::from(base)]);
+    }
+    fn e313(&self) -> Error {
+        return Error::new(Code::E313, vec![]);
+    }
+    fn e314(&self, location: u32, pass_one: u32) -> Error {
+        return Error::new(Code::E314, vec![Argument::Number(location as i64), Argument::Number(pass_one as i64)]);
+    }
+    fn e401(&self) -> Error {
+        return Error::new(Code::E401, vec![]);
+    }
+    fn e402(&self) -> Error {
+        return Error::new(Code::E402, vec![]);
+    }
+    fn e403(&self, name: &str) -> Error {
+        return Error::new(Code::E403, vec![Argument::from(name)]);
+    }
+    fn e404(&self, name: &str, argument: &str) -> Error {
+        return Error::new(Code::E404, vec![Argument::from(name), Argument::from(argument)]);
+    }
+    fn e405(&self, name: &str) -> Error {
+        return Error::new(Code::E405, vec![Argument::from(name)]);
+    }
+    fn e406(&self, parameter: &str) -> Error {
+        return Error::new(Code::E406, vec![Argument::from(parameter)]);
+    }
+    fn e407(&self, label: &str) -> Error {
+        return Error::new(Code::E407, vec![Argument::from(label)]);
+    }
+    fn e408(&self, directive: &str, pair: &str) -> Error {
+        return Error::new(Code::E408, vec![Argument::from(directive), Argument::from(pair)]);
+    }
+    fn e409(&self, condition: &str) -> Error {
+        return Error::new(Code::E409, vec![Argument::from(condition)]);
+    }
+    fn e410(&self, condition: &str) -> Error {
+        return Error::new(Code::E410, vec![Argument::from(condition)]);
+    }
+    fn e411(&self, file: &str) -> Error {
+        return Error::new(Code::E411, vec![Argument::from(file)]);
+    }
+    fn e412(&self, files: &str) -> Error {
+        return Error::new(Code::E412, vec![Argument::from(files)]);
+    }
+    fn e501(&self, file: &str, record: &str) -> Error {
+        return Error::new(Code::E501, vec![Argument::from(file), Argument::from(record)]);
+    }
+    fn e502(&self, symbol: &str) -> Error {
+        return Error::new(Code::E502, vec![Argument::from(symbol)]);
+    }
+    fn e503(&self, symbol: &str, section: &str) -> Error {
+        return Error::new(Code::E503, vec![Argument::from(symbol), Argument::from(section)]);
+    }
+    fn e504(&self, address: u32, section: &str) -> Error {
+        return Error::new(Code::E504, vec![Argument::Number(address as i64), Argument::from(section)]);
+    }
+    fn e505(&self, address: &str) -> Error {
+        return Error::new(Code::E505, vec![Argument::from(address)]);
+    }
+    fn e506(&self, path: &str, section: &str) -> Error {
+        return Error::new(Code::E506, vec![Argument::from(path), Argument::from(section)]);
+    }
+    fn e601(&self, opcode: u8, address: u32) -> Error {
+        return Error::new(Code::E601, vec![Argument::Number(opcode as i64), Argument::Number(address as i64)]);
+    }
+    fn e602(&self, address: u32) -> Error {
+        return Error::new(Code::E602, vec![Argument::Number(address as i64)]);
+    }
+    fn e603(&self, address: u32) -> Error {
+        return Error::new(Code::E603, vec![Argument::Number(address as i64)]);
+    }
+    fn e604(&self, steps: u64) -> Error {
+        return Error::new(Code::E604, vec![Argument::Number(steps as i64)]);
+    }
+    fn e605(&self, size: u32) -> Error {
+        return Error::new(Code::E605, vec![Argument::Number(size as i64)]);
+    }
+    fn e606(&self, value: &str, option: &str) -> Error {
+        return Error::new(Code::E606, vec![Argument::from(value), Argument::from(option)]);
+    }
+    fn e607(&self, device: u8, path: &str, msg: &str) -> Error {
+        return Error::new(Code::E607, vec![Argument::Number(device as i64), Argument::from(path), Argument::from(msg)]);
+    }
+    fn e608(&self, path: &str) -> Error {
+        return Error::new(Code::E608, vec![Argument::from(path)]);
+    }
+    fn e609(&self, text: &str) -> Error {
+        return Error::new(Code::E609, vec![Argument::from(text)]);
+    }
+    fn e610(&self, device: u8, path: &str) -> Error {
+        return Error::new(Code::E610, vec![Argument::Number(device as i64), Argument::from(path)]);
+    }
+    fn e999(&self, msg: &str) -> Error {
+        return Error::new(Code::E999, vec![Argument::from(msg)]);
+    }
+    fn w001(&self, symbol: &str) -> Error {
+        return Error::new(Code::W001, vec![Argument::from(symbol)]);
+    }
+    fn w002(&self, mnemonic: &str) -> Error {
+        return Error::new(Code::W002, vec![Argument::from(mnemonic)]);
+    }
+    fn w003(&self, mnemonic: &str) -> Error {
+        return Error::new(Code::W003, vec![Argument::from(mnemonic)]);
+    }
+    fn w004(&self, operand: &str) -> Error {
+        return Error::new(Code::W004, vec![Argument::from(operand)]);
+    }
+    fn w005(&self, base: &str) -> Error {
+        return Error::new(Code::W005, vec![Argument::from(base)]);
+    }
 }
 
 impl EN {
@@ -315,8 +1230,8 @@ fn lang() -> &'static str {
     return lang;
 }
 
-pub fn handler() -> Box<dyn ErrMsg> {
-    let handler: Box<dyn ErrMsg>;
+pub fn handler() -> Box<dyn ErrMsg<Output = String>> {
+    let handler: Box<dyn ErrMsg<Output = String>>;
 
     match lang() {
         "zh" => {
@@ -332,3 +1247,16 @@ pub fn handler() -> Box<dyn ErrMsg> {
 
     return handler;
 }
+
+// help of a diagnostic with a suggestion
+pub fn help(suggestion: &str) -> String {
+    match lang() {
+        "zh" => return format!("是不是要用 {}?", suggestion),
+        _ => return format!("did you mean {}?", suggestion),
+    }
+}
+
+// errors of the assembler, rendered by handler() at the edge
+pub fn error() -> Box<dyn ErrMsg<Output = Error>> {
+    return Box::new(Recorder);
+}
@@ -0,0 +1,286 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+
+use super::err;
+use super::object::{self, ControlSection};
+use super::parser::SizeLimit;
+
+fn help_message(bin_path: &str) -> String {
+    let msg = format!("Usage: {} link <object path>...", bin_path);
+    let msg = format!("{}\n{}", msg, "use -o <out file name> for output file name");
+    let msg = format!("{}\n{}", msg, "use --progaddr <address> for hexadecimal load address, default is 0");
+    let msg = format!("{}\n{}", msg, "use --image to write a memory image instead of an object program");
+
+    return msg;
+}
+
+// memory of the linked control sections
+pub struct Image {
+    pub name: String,
+    // PROGADDR
+    pub start: u32,
+    // bytes from PROGADDR, None is not loaded
+    pub memory: Vec<Option<u8>>,
+    pub execution: u32,
+    // load map, control section or (section, symbol) with address, length of control section
+    pub map: Vec<(String, Option<String>, u32, u32)>,
+}
+
+// link control sections in order, the first one is loaded at progaddr
+pub fn link(sections: &[ControlSection], progaddr: u32) -> Result<Image, String> {
+    // pass 1, external symbol table
+    let mut estab: HashMap<String, u32> = HashMap::new();
+    let mut map: Vec<(String, Option<String>, u32, u32)> = Vec::new();
+    let mut csaddr: Vec<u32> = Vec::new();
+    let mut end = progaddr;
+    for section in sections {
+        if estab.contains_key(&section.name) {
+            return Err(err::handler().e502(&section.name));
+        }
+        estab.insert(section.name.clone(), end);
+        map.push((section.name.clone(), None, end, section.length));
+        for (symbol, address) in &section.definitions {
+            if estab.contains_key(symbol) {
+                return Err(err::handler().e502(symbol));
+            }
+            // addresses of an absolute program start from its start address
+            let address = end + address.wrapping_sub(section.start);
+            estab.insert(symbol.clone(), address);
+            map.push((section.name.clone(), Some(symbol.clone()), address, 0));
+        }
+        csaddr.push(end);
+        end = match end.checked_add(section.length) {
+            Some(end) if end <= SizeLimit::Location as u32 + 1 => end,
+            _ => return Err(err::handler().e306()),
+        };
+    }
+
+    // pass 2, load T records and apply M records
+    let mut memory: Vec<Option<u8>> = vec![None; (end - progaddr) as usize];
+    let mut execution: Option<u32> = None;
+    for (section, &csaddr) in sections.iter().zip(&csaddr) {
+        // offset of the section and the address range of its records
+        let within = |address: u32, length: u32| -> Result<usize, String> {
+            let (last, end) = (address.checked_add(length), section.start.checked_add(section.length));
+            if address < section.start || last.zip(end).map_or(true, |(last, end)| last > end) {
+                return Err(err::handler().e504(address, &section.name));
+            }
+            return Ok((csaddr - progaddr + address - section.start) as usize);
+        };
+
+        for (address, bytes) in &section.texts {
+            let offset = within(*address, bytes.len() as u32)?;
+            for i in 0..bytes.len() {
+                memory[offset + i] = Some(bytes[i]);
+            }
+        }
+
+        for (address, half_bytes, symbol) in &section.modifications {
+            let value = match symbol {
+                Some((_, name)) => match estab.get(name) {
+                    Some(&value) => value,
+                    None => return Err(err::handler().e503(name, &section.name)),
+                },
+                None => csaddr,
+            };
+            let length = (half_bytes + 1) / 2;
+            let offset = within(*address, length)?;
+
+            let mut field: u32 = 0;
+            for i in 0..length as usize {
+                field = (field << 8) | memory[offset + i].unwrap_or(0) as u32;
+            }
+            let mask = (1u32 << (4 * half_bytes)) - 1;
+            let modified = match symbol {
+                Some(('-', _)) => (field & mask).wrapping_sub(value),
+                _ => (field & mask).wrapping_add(value),
+            };
+            field = (field & !mask) | (modified & mask);
+            for i in (0..length as usize).rev() {
+                memory[offset + i] = Some(field as u8);
+                field >>= 8;
+            }
+        }
+
+        // the first E record with an address is the first instruction
+        if let (None, Some(address)) = (execution, section.execution) {
+            execution = Some(csaddr + address.wrapping_sub(section.start));
+        }
+    }
+
+    return Ok(Image {
+        name: sections.first().map(|s| s.name.clone()).unwrap_or_default(),
+        start: progaddr,
+        memory,
+        execution: execution.unwrap_or(progaddr),
+        map,
+    });
+}
+
+impl Image {
+    // absolute object program with H, T and E records
+    pub fn object_program(&self) -> String {
+        let mut contents = format!(
+            "H^{:6}{:06X}{:06X}\n",
+            self.name,
+            self.start,
+            self.memory.len()
+        );
+
+        // a T record ends at 30 bytes or bytes not loaded
+        let mut i = 0;
+        while i < self.memory.len() {
+            if self.memory[i].is_none() {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            let mut text = String::new();
+            while i < self.memory.len() && i - start < 30 {
+                match self.memory[i] {
+                    Some(byte) => text.push_str(&format!("{:02X}", byte)),
+                    None => break,
+                }
+                i += 1;
+            }
+            contents.push_str(&format!(
+                "T^{:06X}^{:02X}^{}\n",
+                self.start + start as u32,
+                i - start,
+                text
+            ));
+        }
+        contents.push_str(&format!("E^{:06X}", self.execution));
+
+        return contents;
+    }
+
+    // 16 bytes per line, xx is not loaded
+    pub fn memory_image(&self) -> String {
+        let mut contents = String::new();
+        for (n, chunk) in self.memory.chunks(16).enumerate() {
+            contents.push_str(&format!("{:06X} ", self.start + (n * 16) as u32));
+            for (i, byte) in chunk.iter().enumerate() {
+                if i % 4 == 0 {
+                    contents.push_str(" ");
+                }
+                match byte {
+                    Some(byte) => contents.push_str(&format!("{:02X}", byte)),
+                    None => contents.push_str("xx"),
+                }
+            }
+            contents.push_str("\n");
+        }
+
+        return contents;
+    }
+
+    pub fn load_map(&self) -> String {
+        let mut contents = format!(
+            "{:<8} {:<8} {:<7} {}\n",
+            "Section", "Symbol", "Address", "Length"
+        );
+        for (section, symbol, address, length) in &self.map {
+            let row = match symbol {
+                Some(symbol) => format!("{:<8} {:<8} {:06X}", "", symbol, address),
+                None => format!("{:<8} {:<8} {:06X}  {:06X}", section, "", address, length),
+            };
+            contents.push_str(&format!("{}\n", row));
+        }
+
+        return contents;
+    }
+}
+
+pub struct Linker {
+    object_file_paths: Vec<String>,
+    output_file_path: String,
+    progaddr: u32,
+    image: bool,
+}
+
+impl Linker {
+    // args[1] is link
+    pub fn new(args: &[String]) -> Result<Linker, String> {
+        let mut object_file_paths: Vec<String> = Vec::new();
+        let mut output_file_name: Option<String> = None;
+        let mut progaddr = 0;
+        let mut image = false;
+
+        let mut i = 2;
+        while i < args.len() {
+            if args[i] == "--image" {
+                image = true;
+            } else if args[i] == "-o" || args[i] == "--progaddr" {
+                if i + 1 >= args.len() {
+                    return Err(help_message(args[0].as_str()));
+                }
+                let value = &args[i + 1];
+                if args[i] == "-o" {
+                    let re = Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
+                    if !re.is_match(value) {
+                        return Err(err::handler().e001());
+                    }
+                    output_file_name = Some(value.clone());
+                } else {
+                    match u32::from_str_radix(value, 16) {
+                        Ok(address) if address <= SizeLimit::Location as u32 => progaddr = address,
+                        _ => return Err(err::handler().e505(value)),
+                    }
+                }
+                i += 1;
+            } else if args[i].starts_with('-') {
+                return Err(help_message(args[0].as_str()));
+            } else {
+                object_file_paths.push(args[i].clone());
+            }
+            i += 1;
+        }
+
+        if object_file_paths.len() == 0 {
+            return Err(help_message(args[0].as_str()));
+        }
+        let extension = if image { "img" } else { "out" };
+        let output_file_path = format!("{}.{}", output_file_name.unwrap_or(String::from("a")), extension);
+        // a.out of the assembler is overwritten before it is read
+        for path in &object_file_paths {
+            if let (Ok(input), Ok(output)) = (fs::canonicalize(path), fs::canonicalize(&output_file_path)) {
+                if input == output {
+                    return Err(err::handler().e007(&output_file_path));
+                }
+            }
+        }
+
+        Ok(Linker {
+            object_file_paths,
+            output_file_path,
+            progaddr,
+            image,
+        })
+    }
+
+    pub fn run(&self) -> Result<(), String> {
+        let mut sections: Vec<ControlSection> = Vec::new();
+        for path in &self.object_file_paths {
+            match fs::read_to_string(path) {
+                Ok(contents) => sections.extend(object::parse(path, &contents)?),
+                Err(_) => return Err(err::handler().e002()),
+            }
+        }
+
+        let image = link(&sections, self.progaddr)?;
+        println!("{}", image.load_map());
+
+        let contents = if self.image {
+            image.memory_image()
+        } else {
+            image.object_program()
+        };
+        if let Err(e) = fs::write(&self.output_file_path, contents) {
+            return Err(err::handler().e003(&e.to_string()));
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::fs;
+
+use super::err::{self, Error};
+use super::parser::{Code, Parser};
+use super::{Line, ObjectCode};
+
+// object code of a statement or a literal, after the addresses are known
+struct Row {
+    statement: u32,
+    // index of the control section
+    section: usize,
+    location: u32,
+    byte: u32,
+    obj_code: Option<String>,
+    literal: Option<String>,
+}
+
+pub struct Listing {
+    rows: Vec<Row>,
+    errors: Vec<(u32, Error)>,
+    section: usize,
+    // statement, program block and location of the statements in the control section
+    blocked: Vec<(u32, usize, u32)>,
+    // address of each statement, a statement without object code is listed at it
+    addresses: HashMap<u32, u32>,
+}
+
+impl Listing {
+    pub fn new() -> Listing {
+        Listing {
+            rows: Vec::new(),
+            errors: Vec::new(),
+            section: 0,
+            blocked: Vec::new(),
+            addresses: HashMap::new(),
+        }
+    }
+
+    pub fn add_code(&mut self, code: &Code) {
+        let obj_code = if code.no_obj_code {
+            None
+        } else {
+            let width = (code.byte * 2) as usize;
+            Some(format!("{:0width$X}", code.obj_code, width = width))
+        };
+
+        self.rows.push(Row {
+            statement: code.line_number,
+            section: self.section,
+            location: code.location,
+            byte: code.byte,
+            obj_code: obj_code,
+            // literal pool placed by LTORG or END
+            literal: code.source_code.strip_prefix("*\t").map(String::from),
+        });
+    }
+
+    pub fn add_location(&mut self, statement: u32, block: usize, location: u32) {
+        self.blocked.push((statement, block, location));
+    }
+
+    // codes added later belong to the next control section,
+    // the program blocks of the section are placed now
+    pub fn end_section(&mut self, parser: &Parser) {
+        for (statement, block, location) in self.blocked.drain(..) {
+            if let Some(address) = parser.block_address(block, location) {
+                self.addresses.insert(statement, address);
+            }
+        }
+        self.section += 1;
+    }
+
+    // control section, location, length and statement of each object code
+    pub fn locations(&self) -> Vec<(usize, u32, u32, u32)> {
+        return self
+            .rows
+            .iter()
+            .filter(|row| row.obj_code.is_some())
+            .map(|row| (row.section, row.location, row.byte, row.statement))
+            .collect();
+    }
+
+    // statement and object code of each row
+    pub fn codes(&self) -> Vec<(u32, ObjectCode)> {
+        let mut codes: Vec<(u32, ObjectCode)> = Vec::new();
+        for row in &self.rows {
+            if let Some(obj_code) = &row.obj_code {
+                let bytes = (0..obj_code.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&obj_code[i..i + 2], 16).unwrap_or(0))
+                    .collect();
+                codes.push((
+                    row.statement,
+                    ObjectCode {
+                        section: row.section,
+                        address: row.location,
+                        bytes,
+                        literal: row.literal.clone(),
+                    },
+                ));
+            }
+        }
+        return codes;
+    }
+
+    pub fn errors(&self) -> &[(u32, Error)] {
+        return &self.errors;
+    }
+
+    // errors of statement 0 don't belong to a line
+    pub fn add_error(&mut self, statement: u32, error: Error) {
+        self.errors.push((statement, error));
+    }
+
+    // errors of pass 1 are found first and cause the location mismatch of pass 2,
+    // a statement with an error of pass 1 keeps the errors of pass 1 as in one-pass mode
+    pub fn add_pass_one_errors(&mut self, errors: Vec<(u32, Error)>) {
+        if errors.is_empty() {
+            return;
+        }
+        let pass_two = std::mem::replace(&mut self.errors, errors);
+        for (statement, error) in pass_two {
+            let found = self.errors.iter().any(|(s, _)| *s == statement);
+            if error.code != err::Code::E314 && !found {
+                self.errors.push((statement, error));
+            }
+        }
+    }
+
+    // write the listing of source code lines and the symbol table
+    pub fn write(&self, path: &str, lines: &[Line], parser: &Parser) -> Result<(), String> {
+        let mut rows: HashMap<u32, Vec<&Row>> = HashMap::new();
+        for row in &self.rows {
+            rows.entry(row.statement).or_default().push(row);
+        }
+
+        let mut contents = format!(
+            "{:>5}  {:<4}  {:<8} {:<8} {:<20} {}\n",
+            "Line", "Loc", "Label", "Mnemonic", "Operand", "Object code"
+        );
+
+        for (index, line) in lines.iter().enumerate() {
+            let statement = (index + 1) as u32;
+            // lines of an included file, or back to the including file
+            if index > 0 && line.file != lines[index - 1].file {
+                contents.push_str(&format!("{:7}File: {}\n", "", line.file));
+            }
+            let (label, mnemonic, operand) = parser.statement(&line.code);
+            let line_rows = rows.remove(&statement).unwrap_or_default();
+            // lines expanded from a macro are marked with +, skipped lines with -
+            let marker = if line.skipped {
+                "-"
+            } else if line.expanded {
+                "+"
+            } else {
+                " "
+            };
+            let number = format!("{:>5}{}", line.line_number, marker);
+
+            if mnemonic == "" {
+                // comment, empty line, skipped line or directive of the macro processor
+                let row = format!("{} {:<4}  {}", number, "", line.source_code);
+                contents.push_str(&format!("{}\n", row.trim_end()));
+            } else {
+                let address = self.addresses.get(&statement).copied().or(line.location);
+                let statement = line_rows.iter().find(|row| row.literal.is_none());
+                // EQU is listed with its value and START with its start address
+                let location = match statement {
+                    Some(row) if row.byte > 0 || mnemonic == "EQU" => Some(row.location),
+                    _ if mnemonic == "START" => u32::from_str_radix(&operand, 16).ok().or(line.location),
+                    _ => address,
+                };
+                let obj_code = statement.and_then(|row| row.obj_code.clone());
+
+                contents.push_str(&row(
+                    &number,
+                    location,
+                    &label,
+                    &mnemonic,
+                    &operand,
+                    &obj_code.unwrap_or_default(),
+                ));
+            }
+
+            for literal in line_rows.iter().filter(|row| row.literal.is_some()) {
+                contents.push_str(&row(
+                    &number,
+                    Some(literal.location),
+                    "*",
+                    literal.literal.as_ref().unwrap(),
+                    "",
+                    literal.obj_code.as_ref().unwrap(),
+                ));
+            }
+
+            for (_, error) in self.errors.iter().filter(|e| e.0 == statement) {
+                contents.push_str(&format!("{:7}***** {}\n", "", error));
+            }
+        }
+
+        // errors which don't belong to a line
+        let last_statement = lines.len() as u32;
+        for (_, error) in self.errors.iter().filter(|e| e.0 == 0 || e.0 > last_statement) {
+            contents.push_str(&format!("{:7}***** {}\n", "", error));
+        }
+
+        contents.push_str(&format!(
+            "\nSymbol table:\n{:<8} {:<8} {:<6} {}\n",
+            "Section", "Symbol", "Value", "Type"
+        ));
+        for (section, symbol, address, kind) in parser.symbol_list() {
+            let value = match address {
+                Some(address) => format!("{:06X}", address),
+                None => String::from("------"),
+            };
+            contents.push_str(&format!("{:<8} {:<8} {:<6} {}\n", section, symbol, value, kind));
+        }
+
+        if let Err(e) = fs::write(path, contents) {
+            return Err(err::handler().e003(&e.to_string()));
+        }
+
+        return Ok(());
+    }
+}
+
+fn row(
+    number: &str,
+    location: Option<u32>,
+    label: &str,
+    mnemonic: &str,
+    operand: &str,
+    obj_code: &str,
+) -> String {
+    let location = match location {
+        Some(location) => format!("{:04X}", location),
+        None => String::new(),
+    };
+    let row = format!(
+        "{} {:<4}  {:<8} {:<8} {:<20} {}",
+        number, location, label, mnemonic, operand, obj_code
+    );
+
+    return format!("{}\n", row.trim_end());
+}
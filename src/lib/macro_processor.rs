@@ -0,0 +1,520 @@
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+use super::err::{self, Error};
+use super::log;
+use super::parser::expression;
+use super::Line;
+
+// a macro calling itself is stopped at this depth
+const MAX_DEPTH: usize = 64;
+// a WHILE loop is stopped after this number of iterations
+const MAX_ITERATIONS: usize = 4096;
+
+// a macro of the definition table
+struct Definition {
+    // parameter names without & and their default values
+    parameters: Vec<(String, String)>,
+    body: Vec<String>,
+}
+
+// operand of a condition, compared as numbers if both are numbers
+#[derive(PartialEq, PartialOrd)]
+enum Term {
+    Number(i64),
+    Text(String),
+}
+
+// expand macros and conditional assembly before the lines are translated
+pub struct MacroProcessor {
+    definitions: HashMap<String, Definition>,
+    // macro-time variables of SET, without &
+    variables: HashMap<String, String>,
+    // number of expansions, used to generate unique $ labels
+    expansions: u32,
+    space_separator: Regex,
+    prototype_regex: Regex,
+    parameter_regex: Regex,
+    keyword_regex: Regex,
+    label_regex: Regex,
+    variable_regex: Regex,
+    logic_regex: Regex,
+    relation_regex: Regex,
+    verbose: bool,
+}
+
+impl MacroProcessor {
+    pub fn new(verbose: bool) -> MacroProcessor {
+        MacroProcessor {
+            definitions: HashMap::new(),
+            variables: HashMap::new(),
+            expansions: 0,
+            space_separator: Regex::new(r"[ \t]+").unwrap(),
+            prototype_regex: Regex::new(r"^&([A-Z][A-Z0-9]*)(=(.*))?$").unwrap(),
+            parameter_regex: Regex::new(r"&([A-Z][A-Z0-9]*)").unwrap(),
+            keyword_regex: Regex::new(r"^&?([A-Z][A-Z0-9]*)=(.*)$").unwrap(),
+            label_regex: Regex::new(r"\$([A-Z][A-Z0-9]*)").unwrap(),
+            variable_regex: Regex::new(r"^&([A-Z][A-Z0-9]*)$").unwrap(),
+            logic_regex: Regex::new(r"[ \t]+(AND|OR)[ \t]+").unwrap(),
+            relation_regex: Regex::new(r"^(.*?)[ \t]*\b(EQ|NE|LT|LE|GT|GE)[ \t]+(.*)$").unwrap(),
+            verbose: verbose,
+        }
+    }
+
+    // macro definitions, invocations and conditional assembly directives are kept as
+    // comment lines, the expanded lines follow the invocation with the same line number
+    pub fn expand(&mut self, lines: Vec<Line>) -> Vec<Line> {
+        return self.process(&lines, 0);
+    }
+
+    fn process(&mut self, lines: &[Line], depth: usize) -> Vec<Line> {
+        let mut result: Vec<Line> = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let mut line = lines[i].clone();
+            i += 1;
+            let (label, operation, operand) = self.fields(&line.code);
+
+            match operation.as_str() {
+                "MACRO" => {
+                    // the body ends at the MEND of the same level
+                    let mut level = 1;
+                    let mut body: Vec<String> = Vec::new();
+                    let mut definition: Vec<Line> = Vec::new();
+                    while i < lines.len() {
+                        let mut body_line = lines[i].clone();
+                        i += 1;
+                        let (_, operation, _) = self.fields(&body_line.code);
+                        if operation == "MACRO" {
+                            level += 1;
+                        } else if operation == "MEND" {
+                            level -= 1;
+                        }
+                        let code = std::mem::take(&mut body_line.code);
+                        definition.push(body_line);
+                        if level == 0 {
+                            break;
+                        }
+                        body.push(code);
+                    }
+
+                    line.code = String::new();
+                    if label == "" {
+                        line.error = Some(err::error().e401());
+                    } else if level > 0 {
+                        line.error = Some(err::error().e403(&label));
+                    } else {
+                        match self.parameters(&operand) {
+                            Ok(parameters) => {
+                                log::println(&format!("define macro {}", label), self.verbose);
+                                self.definitions.insert(label, Definition { parameters, body });
+                            }
+                            Err(e) => line.error = Some(e),
+                        }
+                    }
+                    result.push(line);
+                    result.extend(definition);
+                }
+                "SET" => {
+                    line.code = String::new();
+                    match self.variable_regex.captures(&label) {
+                        Some(caps) => {
+                            let value = match self.term(&operand) {
+                                Term::Number(n) => n.to_string(),
+                                Term::Text(text) => text,
+                            };
+                            log::println(&format!("set {} to {}", label, value), self.verbose);
+                            self.variables.insert(caps[1].to_string(), value);
+                        }
+                        None => line.error = Some(err::error().e407(&label)),
+                    }
+                    result.push(line);
+                }
+                "IF" => {
+                    line.code = String::new();
+                    let (middle, end) = match self.find_end(lines, i - 1) {
+                        (middle, Some(end)) => (middle, end),
+                        (_, None) => {
+                            line.error = Some(err::error().e408("IF", "ENDIF"));
+                            result.push(line);
+                            continue;
+                        }
+                    };
+                    let condition = match self.condition(&operand) {
+                        Ok(condition) => condition,
+                        Err(e) => {
+                            line.error = Some(e);
+                            false
+                        }
+                    };
+                    result.push(line);
+
+                    let then_end = middle.unwrap_or(end);
+                    let else_start = middle.map_or(end, |middle| middle + 1);
+                    if condition {
+                        result.extend(self.process(&lines[i..then_end], depth));
+                    } else {
+                        result.extend(skip(&lines[i..then_end]));
+                    }
+                    if let Some(middle) = middle {
+                        result.push(comment(&lines[middle]));
+                        if condition {
+                            result.extend(skip(&lines[else_start..end]));
+                        } else {
+                            result.extend(self.process(&lines[else_start..end], depth));
+                        }
+                    }
+                    result.push(comment(&lines[end]));
+                    i = end + 1;
+                }
+                "WHILE" => {
+                    line.code = String::new();
+                    let end = match self.find_end(lines, i - 1) {
+                        (_, Some(end)) => end,
+                        (_, None) => {
+                            line.error = Some(err::error().e408("WHILE", "ENDW"));
+                            result.push(line);
+                            continue;
+                        }
+                    };
+
+                    // the condition is evaluated again after each iteration
+                    let mut body: Vec<Line> = Vec::new();
+                    let mut iterations = 0;
+                    loop {
+                        match self.condition(&operand) {
+                            Ok(true) if iterations >= MAX_ITERATIONS => {
+                                line.error = Some(err::error().e410(&operand));
+                                break;
+                            }
+                            Ok(true) => {
+                                body.extend(self.process(&lines[i..end], depth));
+                                iterations += 1;
+                            }
+                            Ok(false) => break,
+                            Err(e) => {
+                                line.error = Some(e);
+                                break;
+                            }
+                        }
+                    }
+                    result.push(line);
+                    if iterations == 0 {
+                        result.extend(skip(&lines[i..end]));
+                    } else {
+                        result.extend(body);
+                    }
+                    result.push(comment(&lines[end]));
+                    i = end + 1;
+                }
+                "MEND" => {
+                    line.code = String::new();
+                    line.error = Some(err::error().e402());
+                    result.push(line);
+                }
+                "ELSE" | "ENDIF" | "ENDW" => {
+                    let pair = if operation == "ENDW" { "WHILE" } else { "IF" };
+                    line.code = String::new();
+                    line.error = Some(err::error().e408(&operation, pair));
+                    result.push(line);
+                }
+                _ => {
+                    // macro-time variables are replaced by their values
+                    line.code = self.substitute(&line.code);
+                    let (label, operation, operand) = self.fields(&line.code);
+                    if !self.definitions.contains_key(&operation) {
+                        result.push(line);
+                        continue;
+                    }
+
+                    line.code = String::new();
+                    if depth >= MAX_DEPTH {
+                        line.error = Some(err::error().e405(&operation));
+                        result.push(line);
+                        continue;
+                    }
+                    match self.invoke(&operation, &label, &operand) {
+                        Ok(body) => {
+                            log::println(
+                                &format!("{}:\t{}\n-> expand macro {}", line.position(), line.source_code, operation),
+                                self.verbose,
+                            );
+                            let expanded: Vec<Line> = body
+                                .into_iter()
+                                .map(|code| Line {
+                                    file: line.file.clone(),
+                                    line_number: line.line_number,
+                                    location: None,
+                                    source_code: code.clone(),
+                                    code: code,
+                                    expanded: true,
+                                    skipped: false,
+                                    error: None,
+                                })
+                                .collect();
+                            result.push(line);
+                            // expanded lines can invoke or define other macros
+                            result.extend(self.process(&expanded, depth + 1));
+                        }
+                        Err(e) => {
+                            line.error = Some(e);
+                            result.push(line);
+                        }
+                    }
+                }
+            }
+        }
+
+        return result;
+    }
+
+    // label, operation and operand, the operation is empty if it isn't a macro
+    // or a directive of the macro processor
+    fn fields(&self, code: &str) -> (String, String, String) {
+        let comment_offset = code.find('.').unwrap_or(code.len());
+        let code = code[..comment_offset].trim();
+        let fields: Vec<&str> = self.space_separator.splitn(code, 3).collect();
+
+        match fields[0] {
+            "MEND" | "IF" | "ELSE" | "ENDIF" | "WHILE" | "ENDW" => {
+                return (String::new(), fields[0].to_string(), fields[1..].join(" "));
+            }
+            _ => {}
+        }
+        if self.definitions.contains_key(fields[0]) {
+            return (String::new(), fields[0].to_string(), fields[1..].join(" "));
+        }
+        if fields.len() >= 2
+            && (fields[1] == "MACRO"
+                || fields[1] == "MEND"
+                || fields[1] == "SET"
+                || self.definitions.contains_key(fields[1]))
+        {
+            return (fields[0].to_string(), fields[1].to_string(), fields[2..].join(" "));
+        }
+
+        return (String::new(), String::new(), String::new());
+    }
+
+    // ELSE and ENDIF of the IF, or ENDW of the WHILE at index start
+    fn find_end(&self, lines: &[Line], start: usize) -> (Option<usize>, Option<usize>) {
+        let mut level = 0;
+        let mut middle: Option<usize> = None;
+        for i in start + 1..lines.len() {
+            let (_, operation, _) = self.fields(&lines[i].code);
+            match operation.as_str() {
+                "IF" | "WHILE" => level += 1,
+                "ELSE" if level == 0 && middle.is_none() => middle = Some(i),
+                "ENDIF" | "ENDW" if level == 0 => return (middle, Some(i)),
+                "ENDIF" | "ENDW" => level -= 1,
+                _ => {}
+            }
+        }
+
+        return (middle, None);
+    }
+
+    // replace macro-time variables, -> concatenates a variable with the following characters
+    fn substitute(&self, code: &str) -> String {
+        let mut replaced = false;
+        let result = self.parameter_regex.replace_all(code, |caps: &Captures| {
+            match self.variables.get(&caps[1]) {
+                Some(value) => {
+                    replaced = true;
+                    value.clone()
+                }
+                None => caps[0].to_string(),
+            }
+        });
+        if replaced {
+            return result.replace("->", "");
+        }
+
+        return result.to_string();
+    }
+
+    // number, 'text' or other text, after the variables are replaced
+    fn term(&self, text: &str) -> Term {
+        let text = self.substitute(text);
+        let text = text.trim();
+        if text.len() >= 2 && text.starts_with('\'') && text.ends_with('\'') {
+            return Term::Text(text[1..text.len() - 1].to_string());
+        }
+        if let Ok(Some(value)) = expression::evaluate(text, 0, 0, &mut |_| Ok(None)) {
+            if value.is_absolute() {
+                return Term::Number(value.value);
+            }
+        }
+
+        return Term::Text(text.to_string());
+    }
+
+    // (A EQ B), relations are EQ NE LT LE GT GE and joined by AND OR from left to right
+    fn condition(&self, operand: &str) -> Result<bool, Error> {
+        let operand = operand.trim();
+        let inner = match operand.strip_prefix('(').and_then(|o| o.strip_suffix(')')) {
+            Some(inner) => inner.trim(),
+            None => return Err(err::error().e409(operand)),
+        };
+
+        let logic: Vec<&str> = self.logic_regex.find_iter(inner).map(|m| m.as_str().trim()).collect();
+        let mut result = false;
+        for (i, relation) in self.logic_regex.split(inner).enumerate() {
+            let caps = match self.relation_regex.captures(relation) {
+                Some(caps) => caps,
+                None => return Err(err::error().e409(operand)),
+            };
+            let (left, right) = match (self.term(&caps[1]), self.term(&caps[3])) {
+                (Term::Number(l), Term::Text(r)) => (Term::Text(l.to_string()), Term::Text(r)),
+                (Term::Text(l), Term::Number(r)) => (Term::Text(l), Term::Text(r.to_string())),
+                terms => terms,
+            };
+            let value = match &caps[2] {
+                "EQ" => left == right,
+                "NE" => left != right,
+                "LT" => left < right,
+                "LE" => left <= right,
+                "GT" => left > right,
+                _ => left >= right,
+            };
+            result = match i {
+                0 => value,
+                _ if logic[i - 1] == "AND" => result && value,
+                _ => result || value,
+            };
+        }
+
+        return Ok(result);
+    }
+
+    // parameters of the prototype, &NAME or &NAME=default
+    fn parameters(&self, operand: &str) -> Result<Vec<(String, String)>, Error> {
+        let mut parameters: Vec<(String, String)> = Vec::new();
+        if operand == "" {
+            return Ok(parameters);
+        }
+
+        for parameter in operand.split(',') {
+            match self.prototype_regex.captures(parameter) {
+                Some(caps) if !parameters.iter().any(|p| p.0 == &caps[1]) => {
+                    let default = caps.get(3).map_or("", |m| m.as_str());
+                    parameters.push((caps[1].to_string(), default.to_string()));
+                }
+                _ => return Err(err::error().e406(parameter)),
+            }
+        }
+
+        return Ok(parameters);
+    }
+
+    // body of the macro with arguments substituted and unique $ labels
+    fn invoke(&mut self, name: &str, label: &str, operand: &str) -> Result<Vec<String>, Error> {
+        let definition = &self.definitions[name];
+        let mut values: HashMap<&str, String> = definition
+            .parameters
+            .iter()
+            .map(|(name, default)| (name.as_str(), default.clone()))
+            .collect();
+
+        // positional arguments in order, keyword arguments anywhere
+        let mut position = 0;
+        if operand != "" {
+            for argument in operand.split(',') {
+                if let Some(caps) = self.keyword_regex.captures(argument) {
+                    match definition.parameters.iter().find(|p| p.0 == &caps[1]) {
+                        Some((name, _)) => {
+                            values.insert(name.as_str(), caps[2].to_string());
+                            continue;
+                        }
+                        None => return Err(err::error().e404(name, argument)),
+                    }
+                }
+                if position >= definition.parameters.len() {
+                    return Err(err::error().e404(name, argument));
+                }
+                // an omitted argument keeps the default value
+                if argument != "" {
+                    values.insert(definition.parameters[position].0.as_str(), argument.to_string());
+                }
+                position += 1;
+            }
+        }
+
+        let id = unique_id(self.expansions);
+        let mut body: Vec<String> = Vec::new();
+        // label of the invocation is the address of the first line
+        if label != "" {
+            body.push(format!("{} EQU *", label));
+        }
+
+        // $ labels of nested definitions are generated when they are expanded
+        let mut level = 0;
+        for code in &definition.body {
+            let comment_offset = code.find('.').unwrap_or(code.len());
+            if code[..comment_offset].trim() == "" {
+                continue;
+            }
+            let (_, operation, _) = self.fields(code);
+
+            let code = self.parameter_regex.replace_all(code, |caps: &Captures| {
+                match values.get(&caps[1]) {
+                    Some(value) => value.clone(),
+                    None => caps[0].to_string(),
+                }
+            });
+            // -> concatenates a parameter with the following characters
+            let mut code = code.replace("->", "");
+            if level == 0 && operation != "MACRO" {
+                code = self
+                    .label_regex
+                    .replace_all(&code, |caps: &Captures| format!("${}{}", id, &caps[1]))
+                    .to_string();
+            }
+            if operation == "MACRO" {
+                level += 1;
+            } else if operation == "MEND" {
+                level -= 1;
+            }
+            body.push(code);
+        }
+
+        self.expansions += 1;
+        return Ok(body);
+    }
+}
+
+// AA, AB, ..., ZZ, BAA, ...
+fn unique_id(expansions: u32) -> String {
+    let mut id = String::new();
+    let mut n = expansions;
+    loop {
+        id.insert(0, (b'A' + (n % 26) as u8) as char);
+        n /= 26;
+        if id.len() >= 2 && n == 0 {
+            break;
+        }
+    }
+
+    return id;
+}
+
+// lines in the branch which isn't assembled
+fn skip(lines: &[Line]) -> Vec<Line> {
+    return lines
+        .iter()
+        .map(|line| Line {
+            code: String::new(),
+            skipped: true,
+            ..line.clone()
+        })
+        .collect();
+}
+
+// directive of the macro processor kept in the listing
+fn comment(line: &Line) -> Line {
+    return Line {
+        code: String::new(),
+        ..line.clone()
+    };
+}
@@ -1,58 +1,285 @@
 use regex::Regex;
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Write};
+use std::env;
+use std::io::{self, IsTerminal, Write};
 use std::path::Path;
+use std::thread;
 
+mod linker;
+pub use linker::Linker;
+mod listing;
+use listing::Listing;
 mod log;
+mod macro_processor;
+use macro_processor::MacroProcessor;
+mod object;
 mod parser;
 use parser::Code;
 use parser::Parser;
+mod assembly;
+pub use assembly::{assemble, Assembly, Diagnostic, Diagnostics, ListingLine, ObjectCode, Options, Symbol, SymbolKind};
+mod debugger;
+pub use debugger::Debugger;
+mod device;
+mod disassembler;
+pub use disassembler::Disassembler;
+mod simulator;
+pub use simulator::Simulator;
+mod source;
+use source::Source;
 mod err;
+pub use err::{Argument, Code as ErrorCode, Severity};
+use err::{Error, Field, Severity as ErrorSeverity};
+mod warning;
+pub use warning::Warnings;
+mod xref;
 
 fn help_message(bin_path: &str) -> String {
-    let msg = format!("Usage: {} <code path>", bin_path);
+    let msg = format!("Usage: {} <code path>...", bin_path);
     let msg = format!("{}\n{}", msg, "use -v for more information");
     let msg = format!("{}\n{}", msg, "use -o <out file name> for output file name");
+    let msg = format!("{}\n{}", msg, "use -d <dir> for output directory, object files are named after source files");
+    let msg = format!("{}\n{}", msg, "use --two-pass for two-pass assembly");
+    let msg = format!("{}\n{}", msg, "use -l <listing file> for assembly listing");
+    let msg = format!("{}\n{}", msg, "use --xref for symbol cross reference");
+    let msg = format!("{}\n{}", msg, "use -I <dir> to search included files in dir");
+    let msg = format!("{}\n{}", msg, "use --format <obj|bin> for object program or flat binary memory image, default is obj");
+    let msg = format!("{}\n{}", msg, "use --fill <hex bytes> for the pattern of RESB and RESW in binary, default is 00");
+    let msg = format!("{}\n{}", msg, "use --color <auto|always|never> for colored diagnostics, default is auto on a terminal");
+    let msg = format!("{}\n{}", msg, "use -W<name> or -Wno-<name> to turn a warning on or off, -Werror to make warnings errors");
+    let msg = format!("{}\n{}", msg, "  unreachable, empty-reserve and unused-base are on, unused-label and format4 are off");
+    let msg = format!("{}\n{}", msg, "use link <object path>... to link object programs");
+    let msg = format!("{}\n{}", msg, "use run <object path>... to execute object programs");
+    let msg = format!("{}\n{}", msg, "use debug <code path> to debug a program");
+    let msg = format!("{}\n{}", msg, "use disasm <object path> to disassemble an object program");
 
     return msg;
 }
 
+// a line of source code, the intermediate representation of pass 1,
+// the statement number given to the parser is the index of the line plus 1
+#[derive(Clone)]
+struct Line {
+    file: String,
+    line_number: u32,
+    location: Option<u32>,
+    code: String,
+    source_code: String,
+    // generated by a macro expansion
+    expanded: bool,
+    // in a branch of conditional assembly which isn't assembled
+    skipped: bool,
+    // error found before the line is translated
+    error: Option<Error>,
+}
+
+impl Line {
+    // file:line of diagnostics
+    fn position(&self) -> String {
+        return format!("{}:{}", self.file, self.line_number);
+    }
+}
+
+// a source file after assembly
+struct Output {
+    lines: Vec<Line>,
+    parser: Parser,
+    listing: Listing,
+    // H to E records of all control sections
+    object_program: String,
+    have_error: bool,
+}
+
+impl Output {
+    // errors in the order they are found
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        return self
+            .listing
+            .errors()
+            .iter()
+            .map(|(statement, error)| {
+                let labeled = match (*statement as usize).checked_sub(1).and_then(|i| self.lines.get(i)) {
+                    Some(line) => self.parser.statement(&line.code).0 != "",
+                    None => false,
+                };
+                Diagnostic::new(&self.lines, *statement, error, labeled)
+            })
+            .collect();
+    }
+}
+
 pub struct Target {
-    code_file_path: String,
-    execute_file_path: String,
+    code_file_paths: Vec<String>,
+    // object file of each source file
+    execute_file_paths: Vec<String>,
+    output_dir: Option<String>,
     verbose: bool, // verbose mode -> debug mode
+    two_pass: bool,
+    listing_file_path: Option<String>,
+    xref: bool,
+    include_dirs: Vec<String>,
+    // flat binary from the start address instead of object program
+    binary: bool,
+    // pattern of bytes not loaded in binary
+    fill: Vec<u8>,
+    warnings: Warnings,
+    // diagnostics with ANSI colors
+    color: bool,
 }
 
 impl Target {
+    // one pass assembly without output files, used by debug and disasm
+    fn with_include_dirs(include_dirs: &[String]) -> Target {
+        Target {
+            code_file_paths: Vec::new(),
+            execute_file_paths: Vec::new(),
+            output_dir: None,
+            verbose: false,
+            two_pass: false,
+            listing_file_path: None,
+            xref: false,
+            include_dirs: include_dirs.to_vec(),
+            binary: false,
+            fill: vec![0],
+            warnings: Warnings::default(),
+            color: terminal_color(),
+        }
+    }
+
     pub fn new(args: &[String]) -> Result<Target, String> {
         if args.len() < 2 {
             return Err(help_message(args[0].as_str()).into());
         }
         let mut verbose = false;
-        let mut execute_file_path = String::from("a.out");
-        let code_file_path = args[args.len() - 1].clone();
+        let mut two_pass = false;
+        let mut listing_file_path: Option<String> = None;
+        let mut xref = false;
+        let mut include_dirs: Vec<String> = Vec::new();
+        let mut output_dir: Option<String> = None;
+        let mut execute_file_name: Option<String> = None;
+        let mut code_file_paths: Vec<String> = Vec::new();
+        let mut binary = false;
+        let mut fill: Vec<u8> = vec![0];
+        let mut warnings = Warnings::default();
+        let mut color = terminal_color();
 
-        for i in 1..args.len() - 1 {
+        let mut i = 1;
+        while i < args.len() {
             if args[i] == "-v" {
                 verbose = true;
-            } else if args[i] == "-o" {
-                if i + 1 >= args.len() - 1 {
+            } else if args[i] == "--two-pass" {
+                two_pass = true;
+            } else if args[i] == "--xref" {
+                xref = true;
+            } else if args[i] == "-l"
+                || args[i] == "-I"
+                || args[i] == "-o"
+                || args[i] == "-d"
+                || args[i] == "--format"
+                || args[i] == "--fill"
+                || args[i] == "--color"
+            {
+                if i + 1 >= args.len() {
                     return Err(help_message(args[0].as_str()).into());
                 }
-                let re = Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
-                if !re.is_match(&args[i + 1]) {
-                    return Err(err::handler().e001());
+                let value = args[i + 1].clone();
+                if args[i] == "-l" {
+                    listing_file_path = Some(value);
+                } else if args[i] == "-I" {
+                    include_dirs.push(value);
+                } else if args[i] == "-d" {
+                    output_dir = Some(value);
+                } else if args[i] == "--format" {
+                    match value.as_str() {
+                        "obj" => binary = false,
+                        "bin" => binary = true,
+                        _ => return Err(err::handler().e006(&value, &args[i])),
+                    }
+                } else if args[i] == "--color" {
+                    match value.as_str() {
+                        "auto" => color = terminal_color(),
+                        "always" => color = true,
+                        "never" => color = false,
+                        _ => return Err(err::handler().e006(&value, &args[i])),
+                    }
+                } else if args[i] == "--fill" {
+                    let re = Regex::new(r"^([0-9a-fA-F]{2})+$").unwrap();
+                    if !re.is_match(&value) {
+                        return Err(err::handler().e006(&value, &args[i]));
+                    }
+                    fill = (0..value.len())
+                        .step_by(2)
+                        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
+                        .collect();
+                } else {
+                    let re = Regex::new(r"^[a-zA-Z0-9_]+$").unwrap();
+                    if !re.is_match(&value) {
+                        return Err(err::handler().e001());
+                    }
+                    execute_file_name = Some(value);
                 }
-                execute_file_path = args[i + 1].clone();
-                execute_file_path.push_str(".out")
+                i += 1;
+            } else if args[i].starts_with("-W") {
+                if !warnings.set(&args[i]) {
+                    return Err(err::handler().e006(&args[i], "-W"));
+                }
+            } else if args[i].starts_with('-') {
+                return Err(help_message(args[0].as_str()).into());
+            } else {
+                code_file_paths.push(args[i].clone());
             }
+            i += 1;
+        }
+
+        if code_file_paths.len() == 0 {
+            return Err(help_message(args[0].as_str()).into());
+        }
+        if code_file_paths.len() > 1 {
+            if execute_file_name.is_some() {
+                return Err(err::handler().e004("-o"));
+            }
+            if listing_file_path.is_some() {
+                return Err(err::handler().e004("-l"));
+            }
+        }
+
+        // a.out is kept for one source file without -d
+        let extension = if binary { "bin" } else { "out" };
+        let mut execute_file_paths: Vec<String> = Vec::new();
+        for code_file_path in &code_file_paths {
+            let name = match &execute_file_name {
+                Some(name) => format!("{}.{}", name, extension),
+                None if code_file_paths.len() == 1 && output_dir.is_none() => format!("a.{}", extension),
+                None => {
+                    let stem = Path::new(code_file_path).file_stem().unwrap_or_default();
+                    format!("{}.{}", stem.to_string_lossy(), extension)
+                }
+            };
+            let path = match &output_dir {
+                Some(dir) => Path::new(dir).join(name).to_string_lossy().to_string(),
+                None => name,
+            };
+            // source files of the same name in different directories
+            if execute_file_paths.contains(&path) {
+                return Err(err::handler().e008(&path));
+            }
+            execute_file_paths.push(path);
         }
 
         Ok(Target {
-            code_file_path,
-            execute_file_path,
+            code_file_paths,
+            execute_file_paths,
+            output_dir,
             verbose,
+            two_pass,
+            listing_file_path,
+            xref,
+            include_dirs,
+            binary,
+            fill,
+            warnings,
+            color,
         })
     }
 
@@ -70,90 +297,369 @@ __   _(_)_ __   ___ ___ _ __ | |_(_)_ __ | |_| |_ ___| |__
 | (_| \\__ \\__ \\  __/ | | | | | |_) | |  __/ |
  \\__,_|___/___/\\___|_| |_| |_|_.__/|_|\\___|_|
     ");
-        let code_file_path = Path::new(self.code_file_path.as_str());
-        let user_code: String;
-        let comma_regex = Regex::new(r"[ \t]*,[ \t]*").unwrap();
-        let c_re = Regex::new(r"[ \t]+C[ \t]*'").unwrap();
-        let x_re = Regex::new(r"[ \t]+X[ \t]*'").unwrap();
-
-        match fs::read_to_string(code_file_path) {
-            Ok(code) => user_code = code,
-            Err(_) => {
-                return Err(err::handler().e002());
+        if let Some(dir) = &self.output_dir {
+            if let Err(e) = fs::create_dir_all(dir) {
+                return Err(err::handler().e003(&e.to_string()));
+            }
+        }
+        if self.code_file_paths.len() == 1 {
+            if !self.assemble_file(&self.code_file_paths[0], &self.execute_file_paths[0])? {
+                return Err(err::handler().e005(1, 1));
             }
+            return Ok(());
         }
 
-        let mut parser = Parser::new(self.verbose);
+        // source files are assembled in parallel, each with its own parser
+        let results: Vec<Result<bool, String>> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.code_file_paths.len())
+                .map(|i| {
+                    scope.spawn(move || {
+                        self.assemble_file(&self.code_file_paths[i], &self.execute_file_paths[i])
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| match handle.join() {
+                    Ok(result) => result,
+                    Err(_) => Err(err::handler().e999("thread panicked")),
+                })
+                .collect()
+        });
+
+        println!("\nSummary:");
+        let mut failed = 0;
+        for i in 0..results.len() {
+            match &results[i] {
+                Ok(true) => println!("{} -> {}", self.code_file_paths[i], self.execute_file_paths[i]),
+                Ok(false) => {
+                    failed += 1;
+                    println!("{}: has errors, no object file", self.code_file_paths[i]);
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("{}: {}", self.code_file_paths[i], e);
+                }
+            }
+        }
+        println!(
+            "{} source files, {} assembled, {} failed",
+            results.len(),
+            results.len() - failed,
+            failed
+        );
+        if failed > 0 {
+            return Err(err::handler().e005(failed, results.len()));
+        }
+
+        return Ok(());
+    }
+
+    // assemble a source file, return whether the object file is written
+    fn assemble_file(&self, code_file_path: &str, execute_file_path: &str) -> Result<bool, String> {
+        let assembly = self.assemble_source(code_file_path)?;
+        for diagnostic in assembly.diagnostics() {
+            println!("{}", diagnostic.render(self.color));
+        }
+        if let Some(path) = &self.listing_file_path {
+            assembly.listing.write(path, &assembly.lines, &assembly.parser)?;
+        }
+        if self.xref {
+            println!("{}", xref::report(&assembly.lines, &assembly.parser));
+        }
+
+        if !assembly.have_error {
+            let contents = match self.binary {
+                true => self.binary_image(execute_file_path, &assembly.object_program)?,
+                false => assembly.object_program.into_bytes(),
+            };
+            if let Err(e) = fs::write(execute_file_path, contents){
+                return Err(err::handler().e003(&e.to_string()));
+            }
+        }
+
+        return Ok(!assembly.have_error);
+    }
+
+    // memory from the start address to the end of the program, control sections
+    // are linked in order and bytes not loaded by RESB and RESW are filled with the pattern
+    fn binary_image(&self, execute_file_path: &str, object_program: &str) -> Result<Vec<u8>, String> {
+        let sections = object::parse(execute_file_path, object_program)?;
+        let image = linker::link(&sections, sections[0].start)?;
+
+        return Ok(image
+            .memory
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte.unwrap_or(self.fill[i % self.fill.len()]))
+            .collect());
+    }
+
+    // assemble a source file into its object program
+    fn assemble_source(&self, code_file_path: &str) -> Result<Output, Error> {
+        let lines = Source::new(&self.include_dirs).read(code_file_path)?;
+        return self.assemble_lines(lines);
+    }
+
+    // source code lines, pass 1 fills the location of each line
+    fn assemble_lines(&self, lines: Vec<Line>) -> Result<Output, Error> {
+        let mut lines = MacroProcessor::new(self.verbose).expand(lines);
+
+        let mut parser: Parser;
+        let mut pass_one_listing = Listing::new();
+        if self.two_pass {
+            log::println("Pass 1:", self.verbose);
+            let mut pass_one = Parser::new(false);
+            self.assemble(&mut pass_one, &mut lines, &mut pass_one_listing, false)?;
+
+            log::println("Pass 2:", self.verbose);
+            parser = Parser::with_pass_one(self.verbose, pass_one.take_sections());
+        } else {
+            log::println("One pass:", self.verbose);
+            parser = Parser::new(self.verbose);
+        }
+        let mut listing = Listing::new();
+        let (sections, have_error) = self.assemble(&mut parser, &mut lines, &mut listing, true)?;
+        // warnings of pass 1 are found again in pass 2
+        let pass_one_errors: Vec<(u32, Error)> = pass_one_listing
+            .errors()
+            .iter()
+            .filter(|(_, e)| e.code.severity() == ErrorSeverity::Error)
+            .cloned()
+            .collect();
+        let have_error = have_error || !pass_one_errors.is_empty();
+        listing.add_pass_one_errors(pass_one_errors);
+        for (statement, warning) in warning::unused_labels(&lines, &parser) {
+            self.warn(&mut listing, statement, warning);
+        }
+        let have_error = have_error
+            || self.warnings.error
+                && listing.errors().iter().any(|(_, e)| e.code.severity() == ErrorSeverity::Warning);
+
+        // the first control section has the address of the first instruction
+        let mut contents = String::new();
+        for i in 0..sections.len() {
+            if i > 0 {
+                contents.push_str("\n");
+            }
+            contents.push_str(&sections[i]);
+            if i == 0 {
+                contents.push_str(&format!("E^{:06X}", parser.program_start_address));
+            } else {
+                contents.push_str("E");
+            }
+        }
+
+        return Ok(Output {
+            lines,
+            parser,
+            listing,
+            object_program: contents,
+            have_error,
+        });
+    }
 
-        // user code line number
-        let mut line_number: u32 = 1;
+    // translate the source code lines, output is false in pass 1 of two-pass mode.
+    // return the records of control sections and whether there is an error
+    fn assemble(
+        &self,
+        parser: &mut Parser,
+        lines: &mut Vec<Line>,
+        listing: &mut Listing,
+        output: bool,
+    ) -> Result<(Vec<String>, bool), Error> {
+        let verbose = self.verbose && output;
         // memory location
         let mut mem_loc: u32 = 0;
         let mut obj_code_list: Vec<Code> = Vec::new();
-        let mut address_map: HashMap<u32, usize> = HashMap::new();
+        let mut address_map: HashMap<u32, Vec<usize>> = HashMap::new();
         let mut have_error = false;
+        // records of control sections, without E record
+        let mut sections: Vec<String> = Vec::new();
+        for index in 0..lines.len() {
+            let statement = (index + 1) as u32;
+            // symbols found in the tables of pass 1 have the locations of pass 1,
+            // an error of pass 2 can move the location counter by itself
+            if let Some(location) = lines[index].location {
+                if location != mem_loc && !have_error {
+                    listing.add_error(statement, err::error().e314(mem_loc, location));
+                    have_error = true;
+                }
+            }
+            lines[index].location = Some(mem_loc);
 
-        log::println("One pass:", self.verbose);
-
-        for code in user_code.lines() {
-            let source_code = code;
-            let code = code.trim();
-            let code = comma_regex.replace_all(code, ",");
-            let code = c_re.replace_all(code.as_ref(), " C'");
-            let code = x_re.replace_all(code.as_ref(), " X'");
-            let code = code.as_ref();
+            let line = &lines[index];
+            let (position, source_code) = (line.position(), line.source_code.as_str());
 
-            match parser.translate(line_number, mem_loc, code, source_code) {
+            if line.skipped {
+                log::println(&format!("{}:\t{}\n-> skipped", position, source_code), verbose);
+            }
+            let block = parser.block;
+            let result = match &line.error {
+                Some(e) => Err(e.clone()),
+                None => parser.translate(statement, mem_loc, &line.code, source_code),
+            };
+            for warning in parser.warnings.drain(..) {
+                self.warn(listing, statement, warning);
+            }
+            match result {
                 Ok((code, offset, need_modify_code)) => {
                     if parser.program_end && !code.no_obj_code {
-                        log::println(
-                            &format!("{}:\t{}\n-> ", line_number, source_code),
-                            !self.verbose,
-                        );
-                        log::println(&err::handler().e304(), true);
+                        listing.add_error(statement, err::error().e304());
                         break;
                     }
+                    // USE is listed at the location of the program block it switches to
+                    match parser.block != block {
+                        true => {
+                            let location = parser.next_location.unwrap_or(mem_loc);
+                            listing.add_location(statement, parser.block, location);
+                        }
+                        false => listing.add_location(statement, block, mem_loc),
+                    }
                     obj_code_list.push(code);
-                    address_map.insert(mem_loc, obj_code_list.len() - 1);
+                    address_map.entry(mem_loc).or_default().push(obj_code_list.len() - 1);
+
+                    // literal pool placed by LTORG or END
+                    for literal in parser.literal_codes.drain(..) {
+                        address_map.entry(literal.location).or_default().push(obj_code_list.len());
+                        obj_code_list.push(literal);
+                    }
 
+                    // ORG can put more than one code at the same location
                     for i in 0..need_modify_code.len() {
-                        let code = &mut obj_code_list[address_map[&need_modify_code[i]]];
-                        if let Err(e) = code.re_alloc(&mut parser) {
-                            log::print(
-                                &format!("{:X}:{}:\t{}\n-> ",need_modify_code[i], line_number, source_code),
-                                !self.verbose,
-                            );
-                            log::println(&e, true);
+                        for &index in &address_map[&need_modify_code[i]] {
+                            let code = &mut obj_code_list[index];
+                            if let Err(e) = code.re_alloc(parser) {
+                                // errors are traced in verbose mode, diagnostics are printed after assembly
+                                log::print(&format!("{:X}: ", need_modify_code[i]), verbose);
+                                log::println(&e.to_string(), verbose);
+                                // reported once, not again as an undefined symbol
+                                code.undone = false;
+                                have_error = true;
+                                listing.add_error(code.line_number, e);
+                            }
                         }
                     }
 
-                    log::println(&format!("move address {} ", offset), self.verbose);
+                    // control section ends at CSECT
+                    if parser.section_end {
+                        if output {
+                            let (records, error) = self.write_section(parser, &mut obj_code_list, listing, lines)?;
+                            sections.push(records);
+                            have_error = have_error || error;
+                        }
+                        obj_code_list.clear();
+                        address_map.clear();
+                        parser.start_section()?;
+                    }
+
+                    log::println(&format!("move address {} ", offset), verbose);
 
                     mem_loc += offset;
+                    if let Some(location) = parser.next_location.take() {
+                        mem_loc = location;
+                    }
                 }
                 Err(e) => {
-                    if e == err::handler().e301() || e == err::handler().e306(){
+                    if let ErrorCode::E301 | ErrorCode::E306 = e.code {
                         io::stdout().flush().unwrap();
                         return Err(e);
                     }
                     have_error = true;
-                    log::print(
-                        &format!("{}:\t{}\n-> ", line_number, source_code),
-                        !self.verbose,
-                    );
-                    log::println(&e, true);
+                    log::println(&e.to_string(), verbose);
+                    listing.add_error(statement, e);
                 }
             }
-            line_number += 1;
         }
 
         if !parser.program_end {
-            return Err(err::handler().e304());
+            return Err(err::error().e304());
+        }
+
+        if output {
+            let (records, error) = self.write_section(parser, &mut obj_code_list, listing, lines)?;
+            sections.push(records);
+            have_error = have_error || error;
+        }
+
+        return Ok((sections, have_error));
+    }
+
+    // warnings which are turned off are dropped
+    fn warn(&self, listing: &mut Listing, statement: u32, warning: Error) {
+        if self.warnings.enabled(warning.code) {
+            listing.add_error(statement, warning);
+        }
+    }
+
+    // H, D, R, T and M records of the control section
+    fn write_section(
+        &self,
+        parser: &mut Parser,
+        obj_code_list: &mut Vec<Code>,
+        listing: &mut Listing,
+        lines: &[Line],
+    ) -> Result<(String, bool), Error> {
+        let mut have_error = false;
+
+        // addresses of program blocks are known after END
+        for i in 0..obj_code_list.len() {
+            let code = &mut obj_code_list[i];
+            if parser.blocks.len() > 1 {
+                if let Err(e) = code.re_alloc(parser) {
+                    have_error = true;
+                    code.undone = false;
+                    listing.add_error(code.line_number, e);
+                }
+            }
+            if let Some(address) = parser.block_address(code.block, code.location) {
+                code.location = address;
+                code.block = 0;
+            }
+            listing.add_code(code);
+        }
+        listing.end_section(parser);
+
+        // BASE is needed if an instruction uses base relative addressing
+        let based: Vec<&str> = obj_code_list
+            .iter()
+            .filter(|code| !code.no_obj_code && code.byte == 3 && !code.variable && code.xbpe() & 4 != 0)
+            .map(|code| code.base.as_str())
+            .collect();
+        for (statement, base) in parser.bases.drain(..) {
+            if !based.contains(&base.as_str()) {
+                self.warn(listing, statement, err::error().w005(&base).at(Field::Operand));
+            }
+        }
+
+        // format 4 instruction which PC relative or direct addressing of format 3 can reach
+        for code in obj_code_list.iter() {
+            if code.no_obj_code || code.byte != 4 || code.variable || code.undone {
+                continue;
+            }
+            if parser.externals(&code.operand).len() > 0 {
+                continue;
+            }
+            let address = (code.obj_code & 0xFFFFF) as i64;
+            let disp = address - (code.location as i64 + 3);
+            if (code.absolute && address <= 4095) || (!code.absolute && disp >= -2048 && disp <= 2047) {
+                self.warn(listing, code.line_number, err::error().w004(&code.operand).at(Field::Mnemonic));
+            }
+        }
+
+        // codes of program blocks are written in order of address
+        let mut order: Vec<usize> = (0..obj_code_list.len()).collect();
+        if parser.blocks.len() > 1 {
+            order.sort_by_key(|&i| obj_code_list[i].location);
         }
 
         if self.verbose {
             println!("\n\nSymbolTable:");
             parser.symbols_inter();
+            println!("\n\nLiteralTable:");
+            parser.literals_inter();
+            println!("\n\nProgramBlocks:");
+            parser.blocks_inter();
             println!("\n\nObjectCode:");
             for i in 0..obj_code_list.len() {
                 let code = &obj_code_list[i];
@@ -161,7 +667,7 @@ __   _(_)_ __   ___ ___ _ __ | |_(_)_ __ | |_| |_ ___| |__
                     let width = (code.byte * 2) as usize;
                     println!(
                         "{}:{} \n->0x{:04X} {:0width$X}",
-                        code.line_number,
+                        position(lines, code.line_number),
                         code.source_code,
                         code.location,
                         code.obj_code,
@@ -178,13 +684,13 @@ __   _(_)_ __   ___ ___ _ __ | |_(_)_ __ | |_| |_ ___| |__
         match parser.get_symbol_location(program_name.as_str()) {
             Some((s, need_alloc)) => {
                 if need_alloc {
-                    return Err(err::handler().e309());
+                    return Err(err::error().e309());
                 }
                 start_address = s;
                 program_start = s;
             }
             None => {
-                return Err(err::handler().e309());
+                return Err(err::error().e309());
             }
         }
         while program_name.len() < 6 {
@@ -197,25 +703,57 @@ __   _(_)_ __   ___ ___ _ __ | |_(_)_ __ | |_| |_ ___| |__
             parser.program_length - start_address,
         );
 
+        // D record, 6 symbols per record
+        match parser.definitions() {
+            Ok(definitions) => {
+                for chunk in definitions.chunks(6) {
+                    contents.push_str("D");
+                    for (symbol, address) in chunk {
+                        contents.push_str(&format!("^{:6}^{:06X}", symbol, address));
+                    }
+                    contents.push_str("\n");
+                }
+            }
+            Err(e) => {
+                listing.add_error(0, e);
+                have_error = true;
+            }
+        }
+
+        // R record, 12 symbols per record
+        for chunk in parser.extref.chunks(12) {
+            contents.push_str("R");
+            for symbol in chunk {
+                contents.push_str(&format!("^{:6}", symbol));
+            }
+            contents.push_str("\n");
+        }
+
         let mut m_record = String::new();
+        let mut external_record = String::new();
         let mut obj_code = String::new();
         let mut count = 0;
 
-        for i in 0..obj_code_list.len() {
-            let code = &obj_code_list[i];
+        for n in 0..order.len() {
+            let code = &obj_code_list[order[n]];
             let width = (code.byte * 2) as usize;
             if code.undone {
+                let suggestion = parser.nearest_symbol(&code.operand);
                 if code.base != "" {
-                    print!("{}:\t{}\n-> ", code.line_number, code.source_code);
-                    log::println(&err::handler().e312(&code.operand, &code.base), true);
+                    let error = err::error().e312(&code.operand, &code.base);
+                    listing.add_error(code.line_number, error.suggest(suggestion).at(Field::Operand));
                 } else {
-                    print!("{}:\t{}\n-> ", code.line_number, code.source_code);
-                    log::println(&err::handler().e311(&code.operand), true);
+                    let error = err::error().e311(&code.operand);
+                    listing.add_error(code.line_number, error.suggest(suggestion).at(Field::Operand));
                 }
                 have_error = true;
             }
 
-            if (code.no_obj_code && width > 0) || (obj_code.len() - count) + width >= 60 {
+            // the location counter jumped (ORG), need a new T record
+            let jumped = !code.no_obj_code
+                && code.location != start_address + ((obj_code.len() - count) / 2) as u32;
+
+            if (code.no_obj_code && width > 0) || (obj_code.len() - count) + width >= 60 || jumped {
                 if obj_code.len() > 0 {
                     contents.push_str(&format!(
                         "T^{:06X}^{:02X}^{}\n",
@@ -231,24 +769,27 @@ __   _(_)_ __   ___ ___ _ __ | |_(_)_ __ | |_| |_ ___| |__
 
             // RESW RESB
             if code.no_obj_code && width > 0 {
-                let mut now = i + 1;
+                let mut now = n + 1;
 
-                while now < obj_code_list.len() {
-                    let code = &obj_code_list[now];
+                while now < order.len() {
+                    let code = &obj_code_list[order[now]];
                     if code.no_obj_code {
                         now = now + 1;
                     } else {
                         break;
                     }
                 }
-                if now < obj_code_list.len() {
-                    start_address = (&obj_code_list[now]).location;
+                if now < order.len() {
+                    start_address = (&obj_code_list[order[now]]).location;
                 }
                 continue;
             }
 
             if !code.no_obj_code {
-                if code.byte == 4 && !code.variable && code.ni != 1 {
+                if code.variable && code.byte == 3 && !code.absolute {
+                    // WORD with relative expression
+                    m_record.push_str(&format!("M^{:06X}^{:02}\n", code.location, 6));
+                } else if code.byte == 4 && !code.variable && !code.absolute {
                     if code.xbpe() % 2 == 1 {
                         m_record.push_str(&format!(
                             "M^{:06X}^{:02}\n",
@@ -263,6 +804,24 @@ __   _(_)_ __   ___ ___ _ __ | |_(_)_ __ | |_| |_ ___| |__
                         ));
                     }
                 }
+
+                // external references are modified by the linking loader
+                if code.byte == 3 && code.variable || code.byte == 4 && !code.variable {
+                    let (location, length) = if code.variable {
+                        (code.location, 6)
+                    } else {
+                        (code.location + 1, 5)
+                    };
+                    for (symbol, count) in parser.externals(&code.operand) {
+                        let sign = if count > 0 { '+' } else { '-' };
+                        for _ in 0..count.abs() {
+                            external_record.push_str(&format!(
+                                "M^{:06X}^{:02}^{}{}\n",
+                                location, length, sign, symbol
+                            ));
+                        }
+                    }
+                }
                 count += 1;
                 obj_code.push_str(&format!("{:0width$X}^", code.obj_code, width = width));
             }
@@ -280,14 +839,21 @@ __   _(_)_ __   ___ ___ _ __ | |_(_)_ __ | |_| |_ ___| |__
         if program_start == 0x0 {
             contents.push_str(&m_record);
         }
-        contents.push_str(&format!("E^{:06X}", parser.program_start_address));
-        if !have_error {
-            if let Err(e) = fs::write(&self.execute_file_path, contents){
-                return Err(err::handler().e003(&e.to_string()));
-            }
-        }
-        // END print binary code
+        contents.push_str(&external_record);
 
-        return Ok(());
+        return Ok((contents, have_error));
+    }
+}
+
+// colored diagnostics on a terminal, unless NO_COLOR is set
+fn terminal_color() -> bool {
+    return env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+}
+
+// file:line of the statement number of a code
+fn position(lines: &[Line], statement: u32) -> String {
+    match (statement as usize).checked_sub(1).and_then(|i| lines.get(i)) {
+        Some(line) => line.position(),
+        None => statement.to_string(),
     }
 }
@@ -0,0 +1,122 @@
+use super::err;
+
+// a control section of an object program, from H record to E record
+pub struct ControlSection {
+    pub name: String,
+    pub start: u32,
+    pub length: u32,
+    // D record, address relative to the control section
+    pub definitions: Vec<(String, u32)>,
+    // R record
+    pub references: Vec<String>,
+    // T record, address and bytes
+    pub texts: Vec<(u32, Vec<u8>)>,
+    // M record, address, length in half-bytes and the symbol with + or -,
+    // the control section itself without symbol
+    pub modifications: Vec<(u32, u32, Option<(char, String)>)>,
+    // E record, address of the first instruction
+    pub execution: Option<u32>,
+}
+
+// control sections of an object file written by the assembler
+pub fn parse(file: &str, contents: &str) -> Result<Vec<ControlSection>, String> {
+    let mut sections: Vec<ControlSection> = Vec::new();
+    let mut section: Option<ControlSection> = None;
+
+    for record in contents.lines() {
+        let record = record.trim_end();
+        if record.len() == 0 {
+            continue;
+        }
+        let illegal = || err::handler().e501(file, record);
+        let fields: Vec<&str> = record.split('^').collect();
+
+        if fields[0] == "H" {
+            // name, start address and length without separator
+            let header = fields[1..].concat();
+            if section.is_some() || header.len() != 18 || !header.is_char_boundary(6) {
+                return Err(illegal());
+            }
+            section = Some(ControlSection {
+                name: header[..6].trim_end().to_string(),
+                start: hex(&header[6..12]).ok_or_else(illegal)?,
+                length: hex(&header[12..18]).ok_or_else(illegal)?,
+                definitions: Vec::new(),
+                references: Vec::new(),
+                texts: Vec::new(),
+                modifications: Vec::new(),
+                execution: None,
+            });
+            continue;
+        }
+
+        let current = section.as_mut().ok_or_else(illegal)?;
+        match fields[0] {
+            "D" => {
+                if fields.len() % 2 == 0 {
+                    return Err(illegal());
+                }
+                for pair in fields[1..].chunks(2) {
+                    let address = hex(pair[1]).ok_or_else(illegal)?;
+                    current.definitions.push((pair[0].trim_end().to_string(), address));
+                }
+            }
+            "R" => {
+                for symbol in &fields[1..] {
+                    current.references.push(symbol.trim_end().to_string());
+                }
+            }
+            "T" => {
+                if fields.len() < 3 {
+                    return Err(illegal());
+                }
+                let address = hex(fields[1]).ok_or_else(illegal)?;
+                let length = hex(fields[2]).ok_or_else(illegal)?;
+                let text = fields[3..].concat();
+                if text.len() % 2 != 0 || text.len() / 2 != length as usize {
+                    return Err(illegal());
+                }
+                let mut bytes: Vec<u8> = Vec::new();
+                for i in (0..text.len()).step_by(2) {
+                    bytes.push(hex(text.get(i..i + 2).ok_or_else(illegal)?).ok_or_else(illegal)? as u8);
+                }
+                current.texts.push((address, bytes));
+            }
+            "M" => {
+                if fields.len() != 3 && fields.len() != 4 {
+                    return Err(illegal());
+                }
+                let address = hex(fields[1]).ok_or_else(illegal)?;
+                let half_bytes = hex(fields[2]).ok_or_else(illegal)?;
+                let symbol = match fields.get(3) {
+                    Some(symbol) if symbol.starts_with('+') || symbol.starts_with('-') => {
+                        Some((symbol.chars().next().unwrap(), symbol[1..].trim_end().to_string()))
+                    }
+                    Some(_) => return Err(illegal()),
+                    None => None,
+                };
+                if half_bytes == 0 || half_bytes > 6 {
+                    return Err(illegal());
+                }
+                current.modifications.push((address, half_bytes, symbol));
+            }
+            "E" => {
+                if let Some(address) = fields.get(1) {
+                    current.execution = Some(hex(address).ok_or_else(illegal)?);
+                }
+                sections.push(section.take().unwrap());
+            }
+            _ => return Err(illegal()),
+        }
+    }
+
+    if section.is_some() || sections.len() == 0 {
+        return Err(err::handler().e501(file, "E"));
+    }
+
+    return Ok(sections);
+}
+
+fn hex(text: &str) -> Option<u32> {
+    return u32::from_str_radix(text, 16).ok();
+}
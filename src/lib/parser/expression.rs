@@ -0,0 +1,315 @@
+use super::err::{self, Error};
+
+// value of an expression, relative is the number of relative terms left
+// after pairing, 0 => absolute, 1 => relative.
+// relative terms use the offset in their program block, blocks counts the
+// relative terms of each block until the block addresses are known.
+// external references (EXTREF) are 0 in value and counted in externals
+#[derive(Clone)]
+pub struct Value {
+    pub value: i64,
+    pub relative: i32,
+    pub blocks: Vec<(usize, i32)>,
+    pub externals: Vec<(String, i32)>,
+}
+
+impl Value {
+    pub fn absolute(value: i64) -> Value {
+        Value {
+            value: value,
+            relative: 0,
+            blocks: vec![],
+            externals: vec![],
+        }
+    }
+
+    pub fn relative(value: i64, block: usize) -> Value {
+        Value {
+            value: value,
+            relative: 1,
+            blocks: vec![(block, 1)],
+            externals: vec![],
+        }
+    }
+
+    pub fn external(symbol: &str) -> Value {
+        Value {
+            value: 0,
+            relative: 0,
+            blocks: vec![],
+            externals: vec![(String::from(symbol), 1)],
+        }
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        return self.relative == 0;
+    }
+
+    // absolute value which doesn't depend on any block address or external reference
+    pub fn is_constant(&self) -> bool {
+        return self.blocks.iter().all(|&(_, count)| count == 0) && !self.is_external();
+    }
+
+    pub fn is_external(&self) -> bool {
+        return self.externals.iter().any(|(_, count)| *count != 0);
+    }
+
+    // the block of a relative value which only has terms of one block
+    pub fn block(&self) -> Option<usize> {
+        let blocks: Vec<&(usize, i32)> = self.blocks.iter().filter(|b| b.1 != 0).collect();
+        if self.relative == 1 && blocks.len() == 1 {
+            return Some(blocks[0].0);
+        }
+        return None;
+    }
+
+    // None if the value overflows
+    fn add(&self, other: &Value, sign: i32) -> Option<Value> {
+        let mut blocks = self.blocks.clone();
+        for &(block, count) in &other.blocks {
+            match blocks.iter_mut().find(|b| b.0 == block) {
+                Some(b) => b.1 += sign * count,
+                None => blocks.push((block, sign * count)),
+            }
+        }
+
+        let mut externals = self.externals.clone();
+        for (symbol, count) in &other.externals {
+            match externals.iter_mut().find(|e| &e.0 == symbol) {
+                Some(e) => e.1 += sign * count,
+                None => externals.push((symbol.clone(), sign * count)),
+            }
+        }
+
+        let value = other.value.checked_mul(sign as i64)?.checked_add(self.value)?;
+        Some(Value {
+            value: value,
+            relative: self.relative + sign * other.relative,
+            blocks: blocks,
+            externals: externals,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Symbol(String),
+    Operator(char),
+}
+
+// lookup returns the value of a defined symbol, None if not yet
+pub type Lookup<'a> = dyn FnMut(&str) -> Result<Option<Value>, Error> + 'a;
+
+struct Evaluator<'a, 'b> {
+    expression: &'a str,
+    tokens: Vec<Token>,
+    now: usize,
+    location: u32,
+    block: usize,
+    lookup: &'a mut Lookup<'b>,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            match i64::from_str_radix(&number, 10) {
+                Ok(n) => tokens.push(Token::Number(n)),
+                Err(_) => return Err(err::error().e216(expression)),
+            }
+            if i < chars.len() && chars[i].is_ascii_alphabetic() {
+                return Err(err::error().e216(expression));
+            }
+        } else if c.is_ascii_alphabetic() || c == '$' {
+            // $ labels are generated by macro expansion
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            tokens.push(Token::Symbol(chars[start..i].iter().collect()));
+        } else if "+-*/()".contains(c) {
+            tokens.push(Token::Operator(c));
+            i += 1;
+        } else {
+            return Err(err::error().e216(expression));
+        }
+    }
+
+    return Ok(tokens);
+}
+
+// symbols used by the expression, used to clean up waiting lists
+pub fn symbols(expression: &str) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+
+    if let Ok(tokens) = tokenize(expression) {
+        for token in tokens {
+            if let Token::Symbol(symbol) = token {
+                if !symbols.contains(&symbol) {
+                    symbols.push(symbol);
+                }
+            }
+        }
+    }
+
+    return symbols;
+}
+
+// evaluate expression, `*` as a term is the location counter.
+// Ok(None) means some symbol is not defined yet (forward reference)
+pub fn evaluate(
+    expression: &str,
+    location: u32,
+    block: usize,
+    lookup: &mut Lookup,
+) -> Result<Option<Value>, Error> {
+    let tokens = tokenize(expression)?;
+    if tokens.len() == 0 {
+        return Err(err::error().e216(expression));
+    }
+
+    let mut evaluator = Evaluator {
+        expression: expression,
+        tokens: tokens,
+        now: 0,
+        location: location,
+        block: block,
+        lookup: lookup,
+    };
+
+    let value = evaluator.expression()?;
+    if evaluator.now != evaluator.tokens.len() {
+        return Err(err::error().e216(expression));
+    }
+
+    if let Some(value) = &value {
+        if value.relative != 0 && value.relative != 1 {
+            return Err(err::error().e217(expression));
+        }
+    }
+
+    return Ok(value);
+}
+
+impl<'a, 'b> Evaluator<'a, 'b> {
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.now);
+    }
+
+    fn next_is(&self, operator: char) -> bool {
+        return self.peek() == Some(&Token::Operator(operator));
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Option<Value>, Error> {
+        let mut left = self.term()?;
+
+        while self.next_is('+') || self.next_is('-') {
+            let add = self.next_is('+');
+            self.now += 1;
+            let right = self.term()?;
+
+            left = match (left, right) {
+                (Some(l), Some(r)) => {
+                    let sign = if add { 1 } else { -1 };
+                    match l.add(&r, sign) {
+                        Some(value) => Some(value),
+                        None => return Err(err::error().e216(self.expression)),
+                    }
+                }
+                _ => None,
+            };
+        }
+
+        return Ok(left);
+    }
+
+    // term := factor (('*' | '/') factor)*
+    fn term(&mut self) -> Result<Option<Value>, Error> {
+        let mut left = self.factor()?;
+
+        while self.next_is('*') || self.next_is('/') {
+            let multiply = self.next_is('*');
+            self.now += 1;
+            let right = self.factor()?;
+
+            left = match (left, right) {
+                (Some(l), Some(r)) => {
+                    if !l.is_constant() || !r.is_constant() {
+                        return Err(err::error().e217(self.expression));
+                    }
+                    if !multiply && r.value == 0 {
+                        return Err(err::error().e218(self.expression));
+                    }
+                    let value = match multiply {
+                        true => l.value.checked_mul(r.value),
+                        false => l.value.checked_div(r.value),
+                    };
+                    match value {
+                        Some(value) => Some(Value::absolute(value)),
+                        None => return Err(err::error().e216(self.expression)),
+                    }
+                }
+                _ => None,
+            };
+        }
+
+        return Ok(left);
+    }
+
+    // factor := ('+' | '-') factor | '(' expression ')' | number | symbol | '*'
+    fn factor(&mut self) -> Result<Option<Value>, Error> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(err::error().e216(self.expression)),
+        };
+        self.now += 1;
+
+        match token {
+            Token::Number(n) => {
+                return Ok(Some(Value::absolute(n)));
+            }
+            Token::Symbol(symbol) => {
+                return (self.lookup)(&symbol);
+            }
+            Token::Operator('*') => {
+                return Ok(Some(Value::relative(self.location as i64, self.block)));
+            }
+            Token::Operator('(') => {
+                let value = self.expression()?;
+                if !self.next_is(')') {
+                    return Err(err::error().e216(self.expression));
+                }
+                self.now += 1;
+                return Ok(value);
+            }
+            Token::Operator('-') => {
+                let value = match self.factor()? {
+                    Some(value) => value,
+                    None => return Ok(None),
+                };
+                match Value::absolute(0).add(&value, -1) {
+                    Some(value) => return Ok(Some(value)),
+                    None => return Err(err::error().e216(self.expression)),
+                }
+            }
+            Token::Operator('+') => {
+                return self.factor();
+            }
+            _ => {
+                return Err(err::error().e216(self.expression));
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+pub struct LiteralData {
+    obj_code: u64,
+    byte: u32,
+    // location and program block after LTORG or END
+    location: Option<(u32, usize)>,
+    waiting_list: Vec<u32>,
+}
+
+pub struct LiteralTable {
+    table: HashMap<String, LiteralData>,
+    // literals waiting for LTORG or END, in order of first use
+    pool: Vec<String>,
+}
+
+impl LiteralTable {
+    pub fn new() -> LiteralTable {
+        LiteralTable {
+            table: HashMap::new(),
+            pool: Vec::new(),
+        }
+    }
+
+    pub fn contains_key(&self, literal: &str) -> bool {
+        return self.table.contains_key(literal);
+    }
+
+    pub fn insert(&mut self, literal: &str, obj_code: u64, byte: u32) {
+        self.table.insert(
+            literal.to_string(),
+            LiteralData {
+                obj_code: obj_code,
+                byte: byte,
+                location: None,
+                waiting_list: vec![],
+            },
+        );
+        self.pool.push(literal.to_string());
+    }
+
+    pub fn get_location(&self, literal: &str) -> Option<(u32, usize)> {
+        match self.table.get(literal) {
+            Some(data) => data.location,
+            None => None,
+        }
+    }
+
+    pub fn get_value(&self, literal: &str) -> Option<(u64, u32)> {
+        match self.table.get(literal) {
+            Some(data) => Some((data.obj_code, data.byte)),
+            None => None,
+        }
+    }
+
+    pub fn add_waiting(&mut self, literal: &str, obj_code_location: u32) {
+        if let Some(data) = self.table.get_mut(literal) {
+            data.waiting_list.push(obj_code_location);
+        }
+    }
+
+    pub fn remove_waiting(&mut self, literal: &str, location: u32) {
+        if let Some(data) = self.table.get_mut(literal) {
+            data.waiting_list.retain(|&x| x != location);
+        }
+    }
+
+    // literals which are not placed yet, the pool is empty after this
+    pub fn take_pool(&mut self) -> Vec<String> {
+        return self.pool.drain(..).collect();
+    }
+
+    // place the literal at location, return the waiting list
+    pub fn place(&mut self, literal: &str, location: u32, block: usize) -> Vec<u32> {
+        match self.table.get_mut(literal) {
+            Some(data) => {
+                data.location = Some((location, block));
+                return data.waiting_list.drain(..).collect();
+            }
+            None => {
+                return vec![];
+            }
+        }
+    }
+
+    pub fn inter(&self) {
+        self.table.iter().for_each(|(k, v)| {
+            let width = (v.byte * 2) as usize;
+            match v.location {
+                Some((location, block)) => println!(
+                    "{} -> {:04X} R{} : {:0width$X}",
+                    k,
+                    location,
+                    block,
+                    v.obj_code,
+                    width = width
+                ),
+                None => println!("{} -> ???? : {:?}", k, v.waiting_list),
+            }
+        });
+    }
+}
@@ -1,13 +1,17 @@
 use regex::Regex;
 use std::collections::HashMap;
 
-mod opcode_table;
+pub mod expression;
+use expression::Value;
+mod literal_table;
+use literal_table::LiteralTable;
+pub mod opcode_table;
 use opcode_table::OpcodeTable;
+mod suggestion;
 mod symbol_table;
 use symbol_table::SymbolTable;
 
-#[path = "../err.rs"]
-mod err;
+use super::err::{self, Error, Field};
 #[path = "../log/mod.rs"]
 mod log;
 
@@ -17,12 +21,13 @@ pub struct Code {
     _char_mode: bool,
     pub location: u32,
     pub byte: u32,
-    pub ni: u8,
     pub variable: bool,
     pub no_obj_code: bool,
     pub source_code: String,
     pub line_number: u32,
     pub undone: bool,
+    pub absolute: bool,
+    pub block: usize,
     pub base: String,
     pub operand: String,
 }
@@ -32,7 +37,6 @@ impl Code {
         line_number: u32,
         source_code: &str,
         location: u32,
-        ni: u8,
         base: String,
         operand: String,
         obj_code: u64,
@@ -40,12 +44,12 @@ impl Code {
         variable: bool,
         no_obj_code: bool,
         undone: bool,
+        absolute: bool,
     ) -> Code {
         Code {
             line_number: line_number,
             source_code: String::from(source_code),
             location: location,
-            ni: ni,
             base: base,
             operand: String::from(operand),
             obj_code: obj_code,
@@ -53,6 +57,8 @@ impl Code {
             variable: variable,
             no_obj_code: no_obj_code,
             undone: undone,
+            absolute: absolute,
+            block: 0,
             _char_obj_code: String::new(),
             _char_mode: false,
         }
@@ -62,7 +68,6 @@ impl Code {
         Code {
             line_number: line_number,
             source_code: source_code,
-            ni: 0,
             base: String::new(),
             operand: String::new(),
             obj_code: 0,
@@ -71,55 +76,86 @@ impl Code {
             variable: false,
             no_obj_code: true,
             undone: false,
+            absolute: false,
+            block: 0,
             _char_obj_code: String::new(),
             _char_mode: false,
         }
     }
 
-    pub fn re_alloc(&mut self, parser: &mut Parser) -> Result<(), String> {
+    pub fn re_alloc(&mut self, parser: &mut Parser) -> Result<(), Error> {
         let pc: i32;
         let operand_location: u32;
 
+        if !self.undone {
+            return Ok(());
+        }
+
         match self.location.checked_add(self.byte as u32) {
-            Some(new_length) => {
-                pc = new_length as i32;
-            }
+            Some(new_length) => match parser.block_address(self.block, new_length) {
+                Some(address) => {
+                    pc = address as i32;
+                }
+                None => {
+                    // wait for the address of program block
+                    return Ok(());
+                }
+            },
             None => {
-                return Err(err::handler().e306());
+                return Err(err::error().e306().at(Field::Operand));
             }
         }
 
-        match parser.get_symbol_location(&self.operand) {
-            Some((location, need_alloc)) => {
-                if need_alloc {
-                    return Err(err::handler().e999(&format!(
-                        "operand {} is not done, but call re_alloc",
-                        self.operand
-                    )));
-                }
-                operand_location = location;
+        let value: Value;
+        match parser.resolve(&self.operand, self.location, self.block)? {
+            Some(v) => {
+                value = v;
             }
             None => {
-                return Err(err::handler().e999(&format!(
-                    "operand {} is not defined, but call re_alloc",
-                    self.operand
-                )));
+                // still waiting for other symbols of the expression
+                return Ok(());
             }
         }
+        if value.is_external() && !self.variable && self.xbpe() % 2 == 0 {
+            return Err(err::error().e222(&self.operand).at(Field::Operand));
+        }
+        self.absolute = value.is_absolute();
+
+        // WORD
+        if self.variable {
+            if value.value < -8388608 || value.value > 16777215 {
+                return Err(err::error().e205().at(Field::Operand));
+            }
+            self.obj_code = (value.value as u64) & 0xFFFFFF;
+            self.undone = false;
+            return Ok(());
+        }
 
         if self.xbpe() % 2 == 1 {
-            self.obj_code += operand_location as u64;
+            self.obj_code += (value.value as u64) & 0xFFFFF;
             self.undone = false;
             return Ok(());
         }
 
+        // absolute expression (constant) use direct addressing
+        if self.absolute {
+            if value.value < -2048 || value.value > 4095 {
+                return Err(err::error().e215(value.value).at(Field::Operand));
+            }
+            self.obj_code += (value.value as u64) & 0xFFF;
+            self.undone = false;
+            return Ok(());
+        }
+        operand_location = value.value as u32;
+
         let (operand_obj_code, need_alloc, move_xbpe) =
-            parser.fill_operand(self.location, pc, self.base.as_ref(), operand_location)?;
+            parser.fill_operand(self.location, self.block, pc, self.base.as_ref(), operand_location)?;
         if need_alloc {
             return Ok(());
         }
 
-        let xbpe = self.xbpe() + move_xbpe;
+        // x and e are already in obj_code, only add b or p
+        let xbpe = move_xbpe;
         let operand_str: String =
             format!("{:0width$X}", operand_obj_code, width = self.byte as usize);
         let mut operand = String::new();
@@ -143,7 +179,7 @@ impl Code {
         if self.byte <= 2 {
             return 0;
         }
-        let xbpe_bit = format!("{:0width$X}", self.obj_code, width = self.byte as usize)
+        let xbpe_bit = format!("{:0width$X}", self.obj_code, width = (self.byte * 2) as usize)
             .chars()
             .nth(2)
             .unwrap();
@@ -161,16 +197,66 @@ pub enum SizeLimit {
     Location = 0xfffff, //2^20
 }
 
+pub struct Block {
+    pub name: String,
+    // location counter when the block is not in use
+    counter: u32,
+    pub length: u32,
+    pub start: u32,
+}
+
+impl Block {
+    fn new(name: &str) -> Block {
+        Block {
+            name: String::from(name),
+            counter: 0,
+            length: 0,
+            start: 0,
+        }
+    }
+}
+
+// tables of a finished control section
+pub struct Section {
+    name: String,
+    symbol_table: SymbolTable,
+    literal_table: LiteralTable,
+    blocks: Vec<Block>,
+}
+
 pub struct Parser {
     opcode_table: OpcodeTable,
     symbol_table: SymbolTable,
+    literal_table: LiteralTable,
+    pub literal_codes: Vec<Code>,
+    // warnings of the statement, Target takes them after translate
+    pub warnings: Vec<Error>,
+    // statement number and operand of BASE in the control section, Target checks they are needed
+    pub bases: Vec<(u32, String)>,
+    // the last statement is J or RSUB
+    unreachable: bool,
     pub program_name: String,
     program_start: bool,
     pub program_start_address: u32,
     pub program_end: bool,
     pub program_length: u32,
+    // location counter set by ORG, Target moves to it after translate
+    pub next_location: Option<u32>,
+    org_location: Option<u32>,
+    // program blocks (USE), block 0 is the default block
+    pub blocks: Vec<Block>,
+    // program block of the location counter
+    pub block: usize,
+    blocks_resolved: bool,
+    // control sections (CSECT), Target writes the section and calls start_section
+    pub section_end: bool,
+    next_section: Option<String>,
+    sections: Vec<Section>,
+    // tables of pass 1 in two-pass mode, forward references are looked up here
+    pass_one: Vec<Section>,
+    extdef: Vec<String>,
+    pub extref: Vec<String>,
     base: String,
-    wait_for_base: bool,
     space_separator: Regex,
     char_separator: Regex,
     comma_separator: Regex,
@@ -184,15 +270,30 @@ impl Parser {
         Parser {
             opcode_table: OpcodeTable::new(),
             symbol_table: SymbolTable::new(),
+            literal_table: LiteralTable::new(),
+            literal_codes: Vec::new(),
+            warnings: Vec::new(),
+            bases: Vec::new(),
+            unreachable: false,
             program_name: String::new(),
             program_start: false,
             program_start_address: 0x0,
             program_end: false,
             program_length: 0x0,
+            next_location: None,
+            org_location: None,
+            blocks: vec![Block::new("")],
+            block: 0,
+            blocks_resolved: false,
+            section_end: false,
+            next_section: None,
+            sections: Vec::new(),
+            pass_one: Vec::new(),
+            extdef: Vec::new(),
+            extref: Vec::new(),
             base: String::new(),
-            wait_for_base: false,
             space_separator: Regex::new(r"[ \t]+").unwrap(),
-            char_separator: Regex::new(r"C'").unwrap(),
+            char_separator: Regex::new(r"=?C'").unwrap(),
             comma_separator: Regex::new(r",").unwrap(),
             reserve: HashMap::from([
                 ("WORD".to_string(), ()),
@@ -202,6 +303,23 @@ impl Parser {
                 ("RESW".to_string(), ()),
                 ("RESB".to_string(), ()),
                 ("BASE".to_string(), ()),
+                ("NOBASE".to_string(), ()),
+                ("EQU".to_string(), ()),
+                ("LTORG".to_string(), ()),
+                ("ORG".to_string(), ()),
+                ("USE".to_string(), ()),
+                ("CSECT".to_string(), ()),
+                ("EXTDEF".to_string(), ()),
+                ("EXTREF".to_string(), ()),
+                ("MACRO".to_string(), ()),
+                ("MEND".to_string(), ()),
+                ("SET".to_string(), ()),
+                ("IF".to_string(), ()),
+                ("ELSE".to_string(), ()),
+                ("ENDIF".to_string(), ()),
+                ("WHILE".to_string(), ()),
+                ("ENDW".to_string(), ()),
+                ("INCLUDE".to_string(), ()),
             ]),
             registers: HashMap::from([
                 ("A".to_string(), 0x0),
@@ -220,27 +338,360 @@ impl Parser {
         return self.symbol_table.get_location(symbol);
     }
 
+    // parser of pass 2, symbols are looked up in the tables of pass 1
+    pub fn with_pass_one(verbose: bool, pass_one: Vec<Section>) -> Parser {
+        let mut parser = Parser::new(verbose);
+        parser.pass_one = pass_one;
+        return parser;
+    }
+
+    // tables of all control sections, used after END by pass 1
+    pub fn take_sections(&mut self) -> Vec<Section> {
+        self.finish_section();
+        return self.sections.drain(..).collect();
+    }
+
+    fn finish_section(&mut self) {
+        let section = Section {
+            name: self.program_name.clone(),
+            symbol_table: std::mem::replace(&mut self.symbol_table, SymbolTable::new()),
+            literal_table: std::mem::replace(&mut self.literal_table, LiteralTable::new()),
+            blocks: std::mem::replace(&mut self.blocks, vec![Block::new("")]),
+        };
+        self.sections.push(section);
+    }
+
+    // start the control section of the previous CSECT
+    pub fn start_section(&mut self) -> Result<(), Error> {
+        let name = self.next_section.take().unwrap_or_default();
+        self.finish_section();
+
+        self.block = 0;
+        self.blocks_resolved = false;
+        self.section_end = false;
+        self.extdef.clear();
+        self.extref.clear();
+        self.base = String::new();
+        self.org_location = None;
+        self.program_length = 0;
+        self.program_name = name.clone();
+        self.symbol_table.insert(&name, 0, 0)?;
+        self.next_location = Some(0);
+        log::print(&format!("control section: {} ", name), self.verbose);
+
+        return Ok(());
+    }
+
+    // symbols of EXTDEF and their addresses, for D record
+    pub fn definitions(&self) -> Result<Vec<(String, u32)>, Error> {
+        let mut definitions: Vec<(String, u32)> = vec![];
+
+        for symbol in &self.extdef {
+            match self.resolve(symbol, 0, 0) {
+                Ok(Some(value)) if !value.is_external() => {
+                    definitions.push((symbol.clone(), value.value as u32));
+                }
+                _ => {
+                    return Err(err::error().e223(symbol, &self.program_name));
+                }
+            }
+        }
+
+        return Ok(definitions);
+    }
+
+    // external references of an operand and their signs, for M record
+    pub fn externals(&self, expression: &str) -> Vec<(String, i32)> {
+        match self.value_of(expression, 0, 0) {
+            Ok(Some(value)) => {
+                return value.externals.into_iter().filter(|e| e.1 != 0).collect();
+            }
+            _ => {
+                return vec![];
+            }
+        }
+    }
+
+    // address of location in program block, None if the block address is not known yet
+    pub fn block_address(&self, block: usize, location: u32) -> Option<u32> {
+        if block == 0 {
+            return Some(location);
+        }
+        if self.blocks_resolved {
+            return Some(self.blocks[block].start + location);
+        }
+        if let Some(section) = self.pass_one.get(self.sections.len()) {
+            if let Some(pass_one_block) = section.blocks.get(block) {
+                return Some(pass_one_block.start + location);
+            }
+        }
+        return None;
+    }
+
+    // value of a defined symbol, the value of a relative symbol is the offset
+    // in its program block. In pass 2, forward references are defined in pass 1
+    fn lookup(&self, symbol: &str) -> Option<Value> {
+        let mut symbol_table = &self.symbol_table;
+        if symbol_table.get_value(symbol).is_none() {
+            if let Some(section) = self.pass_one.get(self.sections.len()) {
+                symbol_table = &section.symbol_table;
+            }
+        }
+
+        if symbol_table.is_external(symbol) {
+            return Some(Value::external(symbol));
+        }
+        match symbol_table.get_value(symbol) {
+            Some((value, Some(block))) => {
+                return Some(Value::relative(value as i64, block));
+            }
+            Some((value, None)) => {
+                return Some(Value::absolute(value as i32 as i64));
+            }
+            None => {
+                return None;
+            }
+        }
+    }
+
+    // evaluate an expression without registering forward references,
+    // the value is the final address. None if some symbol is still not defined
+    // or the address of its program block is not known yet
+    pub fn resolve(
+        &self,
+        expression: &str,
+        location: u32,
+        block: usize,
+    ) -> Result<Option<Value>, Error> {
+        let value: Value;
+        match self.value_of(expression, location, block)? {
+            Some(v) => {
+                value = v;
+            }
+            None => {
+                return Ok(None);
+            }
+        }
+
+        return Ok(self.address(&value).map(|address| Value {
+            value: address,
+            relative: value.relative,
+            blocks: vec![],
+            externals: value.externals.clone(),
+        }));
+    }
+
+    // add the block addresses to the value, None if some block address is not known yet
+    fn address(&self, value: &Value) -> Option<i64> {
+        let mut address = value.value;
+        for &(block, count) in &value.blocks {
+            if count != 0 {
+                address += count as i64 * self.block_address(block, 0)? as i64;
+            }
+        }
+        return Some(address);
+    }
+
+    // the absolute value of a defined expression, which can depend on block addresses
+    fn absolute_value(&self, value: &Value, expression: &str) -> Result<i64, Error> {
+        if !value.is_absolute() {
+            return Err(err::error().e217(expression).at(Field::Operand));
+        }
+        match self.address(value) {
+            Some(address) => {
+                return Ok(address);
+            }
+            None => {
+                return Err(err::error().e221(expression).at(Field::Operand));
+            }
+        }
+    }
+
+    // value of an expression in the offset of program blocks
+    fn value_of(
+        &self,
+        expression: &str,
+        location: u32,
+        block: usize,
+    ) -> Result<Option<Value>, Error> {
+        if expression.starts_with('=') {
+            let mut literal_location = self.literal_table.get_location(expression);
+            if literal_location.is_none() {
+                if let Some(section) = self.pass_one.get(self.sections.len()) {
+                    literal_location = section.literal_table.get_location(expression);
+                }
+            }
+            return Ok(literal_location.map(|(l, b)| Value::relative(l as i64, b)));
+        }
+
+        return expression::evaluate(expression, location, block, &mut |symbol: &str| {
+            return Ok(self.lookup(symbol));
+        })
+        .map_err(|e| e.at(Field::Operand));
+    }
+
     pub fn symbols_inter(&self) {
         self.symbol_table.inter();
     }
 
+    pub fn literals_inter(&self) {
+        self.literal_table.inter();
+    }
+
+    pub fn blocks_inter(&self) {
+        for i in 0..self.blocks.len() {
+            let block = &self.blocks[i];
+            let name = if block.name == "" { "(default)" } else { block.name.as_str() };
+            println!(
+                "{} {} -> {:04X} length {:04X}",
+                i, name, block.start, block.length
+            );
+        }
+    }
+
+    // separate source code by space and tab, comment is removed
+    fn split(&self, user_code: &str) -> Vec<String> {
+        let mut user_code = String::from(user_code);
+        let comment_offset = user_code.find(".").unwrap_or(user_code.len());
+        let user_code: String = user_code.drain(..comment_offset).collect();
+        let user_code = user_code.trim();
+
+        if user_code.len() == 0 {
+            return vec![];
+        }
+
+        let mut result: Vec<String>;
+        match self.char_separator.find(&user_code) {
+            Some(mat) => {
+                let code_1 = &user_code[..mat.start()];
+                let code_1 = code_1.trim();
+                let code_2 = &user_code[mat.start()..];
+                result = self.space_separator.split(code_1).map(String::from).collect();
+                result.push(String::from(code_2));
+            }
+            None => {
+                result = self.space_separator.split(&user_code).map(String::from).collect();
+            }
+        }
+
+        return result;
+    }
+
+    // label, mnemonic and operand of source code, in the same way as translate
+    pub fn statement(&self, user_code: &str) -> (String, String, String) {
+        let fields = self.split(user_code);
+
+        match fields.len() {
+            0 => {
+                return (String::new(), String::new(), String::new());
+            }
+            1 => {
+                return (String::new(), fields[0].clone(), String::new());
+            }
+            2 => {
+                if fields[1] == "CSECT" || self.opcode_table.contains_key(&fields[1]) {
+                    return (fields[0].clone(), fields[1].clone(), String::new());
+                }
+                return (String::new(), fields[0].clone(), fields[1].clone());
+            }
+            _ => {
+                return (fields[0].clone(), fields[1].clone(), fields[2..].join(" "));
+            }
+        }
+    }
+
+    // symbols used by the operand of source code
+    pub fn references(&self, user_code: &str) -> Vec<String> {
+        let (_, mnemonic, operand) = self.statement(user_code);
+        let mut references: Vec<String> = vec![];
+
+        match mnemonic.as_str() {
+            "START" | "CSECT" | "USE" | "BYTE" | "EXTREF" => {
+                return references;
+            }
+            _ => {}
+        }
+        if operand.starts_with("=C'") || operand.starts_with("=X'") {
+            return references;
+        }
+
+        let operand = operand.trim_start_matches(|c| c == '#' || c == '@' || c == '=');
+        for part in self.comma_separator.split(operand) {
+            for symbol in expression::symbols(part) {
+                if !self.registers.contains_key(&symbol) && !references.contains(&symbol) {
+                    references.push(symbol);
+                }
+            }
+        }
+
+        return references;
+    }
+
+    // mnemonic or directive which is the nearest to a misspelled mnemonic
+    // a directive here is one which needs a label, such as WORD, EQU and CSECT
+    fn unknown_mnemonic(&self, mnemonic: &str) -> Error {
+        if self.reserve.contains_key(mnemonic) {
+            return err::error().e224(mnemonic).at(Field::Mnemonic);
+        }
+        let directives = self.reserve.keys().map(|directive| directive.as_str());
+        let suggestion = suggestion::nearest(mnemonic, self.opcode_table.mnemonics().chain(directives));
+        return err::error().e201(mnemonic).suggest(suggestion).at(Field::Mnemonic);
+    }
+
+    fn nearest_register(&self, register: &str) -> Option<String> {
+        return suggestion::nearest(register, self.registers.keys().map(|register| register.as_str()));
+    }
+
+    // defined symbol which is the nearest to the first undefined symbol of an expression
+    pub fn nearest_symbol(&self, expression: &str) -> Option<String> {
+        let symbol = expression::symbols(expression)
+            .into_iter()
+            .find(|symbol| self.lookup(symbol).is_none())?;
+        return self.symbol_table.nearest(&symbol);
+    }
+
+    // symbols of all control sections sorted by name,
+    // (section, symbol, address, kind) and the address of an undefined symbol is None
+    pub fn symbol_list(&self) -> Vec<(String, String, Option<u32>, char)> {
+        let mut list: Vec<(String, String, Option<u32>, char)> = vec![];
+        let mut sections: Vec<(&str, &SymbolTable, &Vec<Block>)> = self
+            .sections
+            .iter()
+            .map(|section| (section.name.as_str(), &section.symbol_table, &section.blocks))
+            .collect();
+        sections.push((self.program_name.as_str(), &self.symbol_table, &self.blocks));
+
+        for (name, symbol_table, blocks) in sections {
+            for symbol in symbol_table.names() {
+                let (address, kind) = match symbol_table.get_value(&symbol) {
+                    _ if symbol_table.is_external(&symbol) => (None, 'E'),
+                    Some((value, Some(block))) => (Some(blocks[block].start + value), 'R'),
+                    Some((value, None)) => (Some(value), 'A'),
+                    None => (None, 'U'),
+                };
+                list.push((String::from(name), symbol, address, kind));
+            }
+        }
+
+        return list;
+    }
+
     pub fn translate(
         &mut self,
         line_number: u32,
         location: u32,
         user_code: &str,
         source_code: &str,
-    ) -> Result<(Code, u32, Vec<u32>), String> {
+    ) -> Result<(Code, u32, Vec<u32>), Error> {
         // return Value
-        let (code, offset, need_modify_code): (Code, u32, Vec<u32>);
+        let (mut code, offset, need_modify_code): (Code, u32, Vec<u32>);
+        // program block of this statement, USE changes self.block
+        let block = self.block;
+        let mut code_block = self.block;
 
-        // remove comment
-        let mut user_code = String::from(user_code);
-        let comment_offset = user_code.find(".").unwrap_or(user_code.len());
-        let user_code: String = user_code.drain(..comment_offset).collect();
-        let user_code = user_code.trim();
+        let fields = self.split(user_code);
 
-        if user_code.len() == 0 {
+        if fields.len() == 0 {
             log::println(
                 &format!("{}:\t{}\n-> {}", line_number, source_code, "Empty line"),
                 self.verbose,
@@ -256,23 +707,16 @@ impl Parser {
             self.verbose,
         );
 
-        // separate source code by space and tab
-        let mut result: Vec<&str>;
-        match self.char_separator.find(&user_code) {
-            Some(mat) => {
-                let code_1 = &user_code[..mat.start()];
-                let code_1 = code_1.trim();
-                let code_2 = &user_code[mat.start()..];
-                result = self.space_separator.split(code_1).collect::<Vec<&str>>();
-                result.push(code_2);
-            }
-            None => {
-                result = self
-                    .space_separator
-                    .split(&user_code)
-                    .collect::<Vec<&str>>();
-            }
+        // an instruction after J or RSUB is reached only by a jump to its label
+        let (label, operation, _) = self.statement(user_code);
+        let operation = operation.trim_start_matches('+');
+        let instruction = self.opcode_table.contains_key(operation);
+        if self.unreachable && instruction && label == "" {
+            self.warnings.push(err::error().w002(operation).at(Field::Mnemonic));
         }
+        self.unreachable = instruction && (operation == "J" || operation == "RSUB");
+
+        let result: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
         let user_code = result.as_slice();
 
         match user_code.len() {
@@ -281,43 +725,70 @@ impl Parser {
                 let (opcode, instruction_format): (u8, u8);
 
                 if !self.program_start {
-                    return Err(err::handler().e301());
+                    return Err(err::error().e301());
                 }
 
-                if self.wait_for_base && mnemonic != "BASE" {
-                    return Err(err::handler().e303());
-                }
-
-                match self.opcode_table.get(mnemonic) {
-                    Some(instruction) => {
-                        (opcode, instruction_format) = *instruction;
+                if mnemonic == "LTORG" {
+                    let (pool_size, waiting_list) = self.flush_literals(line_number, location)?;
+                    offset = pool_size;
+                    code = Code::empty(line_number, String::from(source_code));
+                    need_modify_code = waiting_list;
+                } else if mnemonic == "NOBASE" {
+                    self.base = String::new();
+                    log::print("base is not used", self.verbose);
+                    offset = 0;
+                    code = Code::empty(line_number, String::from(source_code));
+                    need_modify_code = vec![];
+                } else if mnemonic == "USE" {
+                    self.use_block("", location);
+                    offset = 0;
+                    code = Code::empty(line_number, String::from(source_code));
+                    need_modify_code = vec![];
+                } else if mnemonic == "ORG" {
+                    // restore the location counter before ORG
+                    match self.org_location.take() {
+                        Some(previous) => {
+                            self.next_location = Some(previous);
+                        }
+                        None => {
+                            return Err(err::error().e220().at(Field::Mnemonic));
+                        }
                     }
-                    None => {
-                        return Err(err::handler().e201(mnemonic));
+                    offset = 0;
+                    code = Code::empty(line_number, String::from(source_code));
+                    need_modify_code = vec![];
+                } else {
+                    match self.opcode_table.get(mnemonic) {
+                        Some(instruction) => {
+                            (opcode, instruction_format) = *instruction;
+                        }
+                        None => {
+                            return Err(self.unknown_mnemonic(mnemonic));
+                        }
                     }
-                }
 
-                match self.code_translate(location, mnemonic, opcode, "", instruction_format, false)
-                {
-                    Ok((ni, obj_code, finial_operand, undone, byte)) => {
-                        offset = byte as u32;
-                        code = Code::new(
-                            line_number,
-                            source_code,
-                            location,
-                            ni,
-                            self.base.clone(),
-                            finial_operand,
-                            obj_code,
-                            offset,
-                            false,
-                            false,
-                            undone,
-                        );
-                        need_modify_code = vec![];
-                    }
-                    Err(e) => {
-                        return Err(e);
+                    match self.code_translate(location, mnemonic, opcode, "", instruction_format, false)
+                    {
+                        Ok((obj_code, finial_operand, undone, byte, absolute)) => {
+                            offset = byte as u32;
+                            code = Code::new(
+                                line_number,
+                                source_code,
+                                location,
+                                self.base.clone(),
+                                finial_operand,
+                                obj_code,
+                                offset,
+                                false,
+                                false,
+                                undone,
+                                absolute,
+                            );
+                            need_modify_code = vec![];
+                        }
+                        Err(e) => {
+                            return Err(e);
+                        }
                     }
                 }
             }
@@ -326,18 +797,29 @@ impl Parser {
                 let (opcode, instruction_format): (u8, u8);
 
                 if !self.program_start {
-                    return Err(err::handler().e301());
+                    return Err(err::error().e301());
                 }
 
-                // Case 1: no operand
-                if let Some(instruction) = self.opcode_table.get(field_2) {
+                if field_2 == "CSECT" {
+                    let label = field_1;
+                    if let Err(e) = self.symbol_legal(label) {
+                        return Err(e.at(Field::Label));
+                    }
+                    if label.len() > 6 {
+                        return Err(err::error().e305(label).at(Field::Label));
+                    }
+
+                    // literals are placed at the end of each control section
+                    (offset, need_modify_code) = self.flush_literals(line_number, location)?;
+                    self.resolve_blocks(location + offset)?;
+                    self.section_end = true;
+                    self.next_section = Some(String::from(label));
+                    code = Code::empty(line_number, String::from(source_code));
+                } else if let Some(instruction) = self.opcode_table.get(field_2) {
+                    // Case 1: no operand
                     (opcode, instruction_format) = *instruction;
                     let (label, mnemonic) = (field_1, field_2);
 
-                    if self.wait_for_base && mnemonic != "BASE" {
-                        return Err(err::handler().e303());
-                    }
-
                     match self.code_translate(
                         location,
                         mnemonic,
@@ -346,13 +828,12 @@ impl Parser {
                         instruction_format,
                         false,
                     ) {
-                        Ok((ni, obj_code, finial_operand, undone, byte)) => {
+                        Ok((obj_code, finial_operand, undone, byte, absolute)) => {
                             offset = byte as u32;
                             code = Code::new(
                                 line_number,
                                 source_code,
                                 location,
-                                ni,
                                 self.base.clone(),
                                 finial_operand,
                                 obj_code,
@@ -360,79 +841,132 @@ impl Parser {
                                 false,
                                 false,
                                 undone,
+                                absolute,
                             );
                         }
                         Err(e) => {
-                            return Err(e);
+                            return Err(e.at(Field::Operand));
                         }
                     }
 
                     if let Err(e) = self.symbol_legal(label) {
-                        return Err(format!("label invalid: {}", e));
+                        return Err(e.at(Field::Label));
                     }
 
-                    match self.symbol_table.insert(label, location) {
+                    match self.symbol_table.insert(label, location, block) {
                         Ok(waiting_list) => {
                             need_modify_code = waiting_list;
                         }
                         Err(e) => {
-                            return Err(e);
+                            return Err(e.at(Field::Label));
                         }
                     }
                 } else {
                     // Case 2: have operand
                     let (mnemonic, operand) = (field_1, field_2);
 
-                    if self.wait_for_base && mnemonic != "BASE" {
-                        return Err(err::handler().e303());
-                    }
-
                     match mnemonic {
                         "BASE" => {
                             offset = 0;
 
-                            if let Err(e) = self.symbol_legal(operand) {
-                                return Err(format!("operand invalid: {}", e));
-                            }
-                            if self.wait_for_base {
-                                self.wait_for_base = false;
-                            } else {
-                                return Err(err::handler().e302());
-                            }
+                            // the base register can be any expression, it is
+                            // evaluated when the displacement is calculated
+                            self.expression_legal(operand)?;
 
                             self.base = String::from(operand);
+                            self.bases.push((line_number, String::from(operand)));
                             log::print(&format!("now base is {}", operand), self.verbose);
 
                             code = Code::empty(line_number, String::from(source_code));
                             need_modify_code = vec![];
                         }
-                        "END" => {
-                            let (operand_location, need_alloc): (u32, bool);
+                        "LTORG" | "NOBASE" => {
+                            return Err(err::error().e210(mnemonic).at(Field::Operand));
+                        }
+                        "ORG" => {
+                            offset = 0;
+                            self.org(operand, location)?;
+                            code = Code::empty(line_number, String::from(source_code));
+                            need_modify_code = vec![];
+                        }
+                        "EXTDEF" => {
                             offset = 0;
+                            for symbol in self.comma_separator.split(operand) {
+                                if let Err(e) = self.symbol_legal(symbol) {
+                                    return Err(e.at(Field::Operand));
+                                }
+                                self.extdef.push(String::from(symbol));
+                            }
+                            code = Code::empty(line_number, String::from(source_code));
+                            need_modify_code = vec![];
+                        }
+                        "EXTREF" => {
+                            offset = 0;
+                            let mut waiting_list: Vec<u32> = vec![];
+                            for symbol in self.comma_separator.split(operand) {
+                                if let Err(e) = self.symbol_legal(symbol) {
+                                    return Err(e.at(Field::Operand));
+                                }
+                                waiting_list.append(
+                                    &mut self.symbol_table.insert_external(symbol).map_err(|e| e.at(Field::Operand))?,
+                                );
+                                self.extref.push(String::from(symbol));
+                            }
+                            code = Code::empty(line_number, String::from(source_code));
+                            need_modify_code = waiting_list;
+                        }
+                        "USE" => {
+                            offset = 0;
+                            if !self.symbol_table.is_legal(operand) {
+                                return Err(err::error().e101(operand).at(Field::Operand));
+                            }
+                            self.use_block(operand, location);
+                            code = Code::empty(line_number, String::from(source_code));
+                            need_modify_code = vec![];
+                        }
+                        "END" => {
+                            let operand_location: u32;
 
                             if self.program_end {
-                                return Err(err::handler().e303());
+                                return Err(err::error().e304().at(Field::Mnemonic));
                             }
                             self.program_end = true;
 
                             if let Err(e) = self.symbol_legal(operand) {
-                                return Err(format!("operand invalid: {}", e));
+                                return Err(e.at(Field::Operand));
                             }
 
-                            match self.symbol_table.get_location(operand) {
-                                Some(res) => {
-                                    (operand_location, need_alloc) = (res.0, res.1);
-                                }
-                                None => {
-                                    return Err(err::handler().e202());
+                            code = Code::empty(line_number, String::from(source_code));
+                            (offset, need_modify_code) =
+                                self.flush_literals(line_number, location)?;
+                            self.resolve_blocks(location + offset)?;
+
+                            // the first instruction is in the first control section, a label
+                            // of a line with an error of pass 2 is in the tables of pass 1
+                            match self.sections.first() {
+                                Some(section) => {
+                                    let section = match section.symbol_table.get_value(operand) {
+                                        Some(_) => section,
+                                        None => self.pass_one.first().unwrap_or(section),
+                                    };
+                                    match section.symbol_table.get_value(operand) {
+                                        Some((value, Some(block))) => {
+                                            operand_location = section.blocks[block].start + value;
+                                        }
+                                        _ => {
+                                            return Err(err::error().e202().at(Field::Operand));
+                                        }
+                                    }
                                 }
+                                None => match self.resolve(operand, location, block)? {
+                                    Some(value) => {
+                                        operand_location = value.value as u32;
+                                    }
+                                    None => {
+                                        return Err(err::error().e202().at(Field::Operand));
+                                    }
+                                },
                             }
-
-                            if need_alloc {
-                                return Err(err::handler().e202());
-                            }
-                            code = Code::empty(line_number, String::from(source_code));
-                            need_modify_code = vec![];
                             self.program_start_address = operand_location;
                             log::print(
                                 &format!("program execute at 0x{:04X} ", operand_location),
@@ -451,16 +985,12 @@ impl Parser {
                                 mnemonic = &original_mnemonic[..];
                             }
 
-                            if mnemonic == "LDB" {
-                                self.wait_for_base = true;
-                            }
-
                             match self.opcode_table.get(mnemonic) {
                                 Some(instruction) => {
                                     (opcode, instruction_format) = *instruction;
                                 }
                                 None => {
-                                    return Err(err::handler().e201(mnemonic));
+                                    return Err(self.unknown_mnemonic(mnemonic));
                                 }
                             }
                             match self.code_translate(
@@ -471,13 +1001,12 @@ impl Parser {
                                 instruction_format,
                                 extension,
                             ) {
-                                Ok((ni, obj_code, finial_operand, undone, byte)) => {
+                                Ok((obj_code, finial_operand, undone, byte, absolute)) => {
                                     offset = byte as u32;
                                     code = Code::new(
                                         line_number,
                                         source_code,
                                         location,
-                                        ni,
                                         self.base.clone(),
                                         finial_operand,
                                         obj_code,
@@ -485,11 +1014,12 @@ impl Parser {
                                         false,
                                         false,
                                         undone,
+                                        absolute,
                                     );
                                     need_modify_code = vec![];
                                 }
                                 Err(e) => {
-                                    return Err(e);
+                                    return Err(e.at(Field::Operand));
                                 }
                             }
                         }
@@ -502,10 +1032,6 @@ impl Parser {
                 let operand = user_code[2].trim();
                 let (opcode, instruction_format): (u8, u8);
 
-                if self.wait_for_base && mnemonic != "BASE" {
-                    return Err(err::handler().e303());
-                }
-
                 match mnemonic {
                     "START" => {
                         match u32::from_str_radix(operand, 16) {
@@ -513,12 +1039,12 @@ impl Parser {
                                 offset = location;
                             }
                             Err(_) => {
-                                return Err(err::handler().e203(operand));
+                                return Err(err::error().e203(operand).at(Field::Operand));
                             }
                         }
                         self.program_start = true;
                         if label.len() > 6 {
-                            return Err(err::handler().e305(label));
+                            return Err(err::error().e305(label).at(Field::Label));
                         }
                         self.program_name = String::from(label);
                         log::print(
@@ -531,22 +1057,22 @@ impl Parser {
                         code = Code::empty(line_number, String::from(source_code));
                     }
                     "RESB" => {
-                        match u32::from_str_radix(operand, 10) {
-                            Ok(get) => {
-                                offset = get;
-                            }
-                            Err(_) => {
-                                return Err(err::handler().e204());
-                            }
+                        let size = self.evaluate_defined(operand, location, mnemonic)?;
+                        let size = self.absolute_value(&size, operand)?;
+                        if size < 0 || size >= SizeLimit::Location as i64 {
+                            return Err(err::error().e204().at(Field::Operand));
                         }
+                        offset = size as u32;
                         if offset >= SizeLimit::Location as u32 {
-                            return Err(err::handler().e204());
+                            return Err(err::error().e204().at(Field::Operand));
+                        }
+                        if offset == 0 {
+                            self.warnings.push(err::error().w003(mnemonic).at(Field::Operand));
                         }
                         code = Code::new(
                             line_number,
                             source_code,
                             location,
-                            0,
                             self.base.clone(),
                             String::from(operand),
                             0,
@@ -554,34 +1080,33 @@ impl Parser {
                             true,
                             true,
                             false,
+                            false,
                         );
                     }
                     "RESW" => {
-                        let size: u32;
-                        match u32::from_str_radix(operand, 10) {
-                            Ok(get) => {
-                                size = get;
-                            }
-                            Err(_) => {
-                                return Err(err::handler().e204());
-                            }
+                        let size = self.evaluate_defined(operand, location, mnemonic)?;
+                        let size = self.absolute_value(&size, operand)?;
+                        if size < 0 || size >= SizeLimit::Location as i64 {
+                            return Err(err::error().e204().at(Field::Operand));
                         }
-                        match size.checked_mul(3) {
+                        match (size as u32).checked_mul(3) {
                             Some(get) => {
                                 offset = get;
                             }
                             None => {
-                                return Err(err::handler().e204());
+                                return Err(err::error().e204().at(Field::Operand));
                             }
                         }
                         if offset >= SizeLimit::Location as u32 {
-                            return Err(err::handler().e204());
+                            return Err(err::error().e204().at(Field::Operand));
+                        }
+                        if offset == 0 {
+                            self.warnings.push(err::error().w003(mnemonic).at(Field::Operand));
                         }
                         code = Code::new(
                             line_number,
                             source_code,
                             location,
-                            0,
                             self.base.clone(),
                             String::from(operand),
                             0,
@@ -589,105 +1114,108 @@ impl Parser {
                             true,
                             true,
                             false,
+                            false,
                         );
                     }
                     "WORD" => {
                         offset = 3;
-                        let num: i16;
+                        let (obj_code, undone, absolute): (u64, bool, bool);
 
-                        match i16::from_str_radix(operand, 10) {
-                            Ok(get) => {
-                                num = get;
+                        self.expression_legal(operand)?;
+                        match self.evaluate(operand, location, block)? {
+                            Some(value) => {
+                                if value.value < -8388608 || value.value > 16777215 {
+                                    return Err(err::error().e205().at(Field::Operand));
+                                }
+                                obj_code = (value.value as u64) & 0xFFFFFF;
+                                undone = false;
+                                absolute = value.is_absolute();
                             }
-                            Err(_) => {
-                                return Err(err::handler().e205());
+                            None => {
+                                obj_code = 0;
+                                undone = true;
+                                absolute = false;
                             }
                         }
-                        if num > 2047 || num < -2048 {
-                            return Err(err::handler().e205());
-                        }
-
-                        let operand_str: String = format!("{:03X}", num);
-                        let mut operand = String::new();
-
-                        for i in operand_str.len() - 3..operand_str.len() {
-                            operand.push(operand_str.chars().nth(i).unwrap());
-                        }
-                        let obj_code = u64::from_str_radix(&format!("{}", operand), 16).unwrap();
 
                         code = Code::new(
                             line_number,
                             source_code,
                             location,
-                            0,
                             self.base.clone(),
                             String::from(operand),
                             obj_code,
                             offset,
                             true,
                             false,
-                            false,
+                            undone,
+                            absolute,
                         );
                     }
-                    "BYTE" => {
-                        let mut tmp_obj_code = String::new();
-
-                        if operand.len() <= 3 {
-                            return Err(err::handler().e206());
-                        }
-                        let quote_s = operand.bytes().nth(1).unwrap();
-                        let quote_e = operand.bytes().nth(operand.len() - 1).unwrap();
-                        if quote_s != b'\'' || quote_e != b'\'' {
-                            return Err(err::handler().e206());
-                        }
-
-                        match operand.bytes().nth(0).unwrap() {
-                            b'C' => {
-                                for i in 2..operand.len() - 1 {
-                                    let letter = operand.chars().nth(i).unwrap();
-
-                                    if !letter.is_ascii() {
-                                        return Err(err::handler().e207());
-                                    }
-
-                                    tmp_obj_code.push_str(&format!("{:02X}", letter as u8));
-                                }
-                            }
-                            b'X' => {
-                                if (operand.len() - 3) % 2 != 0 {
-                                    return Err(err::handler().e209());
+                    "ORG" => {
+                        offset = 0;
+                        self.org(operand, location)?;
+                        code = Code::empty(line_number, String::from(source_code));
+                    }
+                    "EQU" => {
+                        offset = 0;
+
+                        let result = self.evaluate_defined(operand, location, mnemonic)?;
+                        let (value, absolute): (u32, bool);
+                        if result.is_absolute() {
+                            (value, absolute) = (self.absolute_value(&result, operand)? as u32, true);
+                            code_block = 0;
+                        } else if let Some(b) = result.block() {
+                            // relative to the program block
+                            (value, absolute) = (result.value as u32, false);
+                            code_block = b;
+                        } else {
+                            match self.address(&result) {
+                                Some(address) => {
+                                    (value, absolute) = (address as u32, false);
+                                    code_block = 0;
                                 }
-                                for i in 2..operand.len() - 1 {
-                                    tmp_obj_code.push(operand.chars().nth(i).unwrap());
+                                None => {
+                                    return Err(err::error().e221(operand).at(Field::Operand));
                                 }
                             }
-                            _ => {
-                                return Err(err::handler().e206());
-                            }
                         }
+                        log::print(&format!("{} is 0x{:04X} ", label, value), self.verbose);
 
-                        if tmp_obj_code.len() > 16 {
-                            return Err(err::handler().e208());
-                        }
-
-                        offset = (tmp_obj_code.len() / 2) as u32;
                         code = Code::new(
                             line_number,
                             source_code,
-                            location,
+                            value,
+                            self.base.clone(),
+                            String::from(operand),
+                            0,
                             0,
+                            true,
+                            true,
+                            false,
+                            absolute,
+                        );
+                    }
+                    "BYTE" => {
+                        let obj_code: u64;
+                        (obj_code, offset) = self.byte_obj_code(operand)?;
+                        code = Code::new(
+                            line_number,
+                            source_code,
+                            location,
                             self.base.clone(),
                             String::from(operand),
-                            u64::from_str_radix(&tmp_obj_code, 16).unwrap(),
+                            obj_code,
                             offset,
                             true,
                             false,
                             false,
+                            true,
                         );
                     }
                     _ => {
                         if !self.program_start {
-                            return Err(err::handler().e301());
+                            return Err(err::error().e301());
                         }
 
                         let original_mnemonic = mnemonic.clone();
@@ -701,16 +1229,12 @@ impl Parser {
                             mnemonic = &original_mnemonic[..];
                         }
 
-                        if mnemonic == "LDB" {
-                            self.wait_for_base = true;
-                        }
-
                         match self.opcode_table.get(mnemonic) {
                             Some(instruction) => {
                                 (opcode, instruction_format) = *instruction;
                             }
                             None => {
-                                return Err(err::handler().e201(mnemonic));
+                                return Err(self.unknown_mnemonic(mnemonic));
                             }
                         }
                         match self.code_translate(
@@ -721,17 +1245,16 @@ impl Parser {
                             instruction_format,
                             extension,
                         ) {
-                            Ok((ni, obj_code, finial_operand, undone, byte)) => {
+                            Ok((obj_code, finial_operand, undone, byte, absolute)) => {
                                 offset = byte as u32;
                                 if finial_operand == label {
-                                    self.symbol_table.remove_waiting(&finial_operand, location);
-                                    return Err(err::handler().e313());
+                                    self.remove_waiting(&finial_operand, location);
+                                    return Err(err::error().e313().at(Field::Operand));
                                 }
                                 code = Code::new(
                                     line_number,
                                     source_code,
                                     location,
-                                    ni,
                                     self.base.clone(),
                                     finial_operand,
                                     obj_code,
@@ -739,42 +1262,53 @@ impl Parser {
                                     false,
                                     false,
                                     undone,
+                                    absolute,
                                 );
                             }
                             Err(e) => {
-                                return Err(e);
+                                return Err(e.at(Field::Operand));
                             }
                         }
                     }
                 }
                 if let Err(e) = self.symbol_legal(label) {
-                    self.symbol_table.remove_waiting(&code.operand, location);
-                    return Err(format!("label invalid: {}", e));
+                    self.remove_waiting(&code.operand, location);
+                    return Err(e.at(Field::Label));
                 }
-                if mnemonic == "START" {
-                    match self.symbol_table.insert(label, offset) {
+                if mnemonic == "EQU" {
+                    let block = if code.absolute { None } else { Some(code_block) };
+                    match self.symbol_table.insert_value(label, code.location, block) {
                         Ok(waiting_list) => {
                             need_modify_code = waiting_list;
                         }
                         Err(e) => {
-                            self.symbol_table.remove_waiting(&code.operand, offset);
-                            return Err(e);
+                            return Err(e.at(Field::Label));
+                        }
+                    }
+                } else if mnemonic == "START" {
+                    match self.symbol_table.insert(label, offset, 0) {
+                        Ok(waiting_list) => {
+                            need_modify_code = waiting_list;
+                        }
+                        Err(e) => {
+                            self.remove_waiting(&code.operand, offset);
+                            return Err(e.at(Field::Label));
                         }
                     }
                 } else {
-                    match self.symbol_table.insert(label, location) {
+                    match self.symbol_table.insert(label, location, block) {
                         Ok(waiting_list) => {
                             need_modify_code = waiting_list;
                         }
                         Err(e) => {
-                            self.symbol_table.remove_waiting(&code.operand, location);
-                            return Err(e);
+                            self.remove_waiting(&code.operand, location);
+                            return Err(e.at(Field::Label));
                         }
                     }
                 }
             }
             _ => {
-                return Err(err::handler().e310());
+                return Err(err::error().e310());
             }
         }
 
@@ -787,48 +1321,60 @@ impl Parser {
         }
         log::println("", self.verbose);
 
-        match self.program_length.checked_add(offset) {
+        // ORG can move backward, program length is the highest location used
+        match location.checked_add(offset) {
             Some(new_length) => {
-                self.program_length = new_length;
+                if new_length > self.blocks[block].length {
+                    self.blocks[block].length = new_length;
+                }
+                if block == 0 && new_length > self.program_length {
+                    self.program_length = new_length;
+                }
             }
             None => {
-                return Err(err::handler().e306());
+                return Err(err::error().e306());
             }
         }
         if self.program_length > SizeLimit::Location as u32 {
-            return Err(err::handler().e306());
+            return Err(err::error().e306());
         }
 
+        code.block = code_block;
+
         Ok((code, offset, need_modify_code))
     }
 
     pub fn fill_operand(
         &mut self,
         location: u32,
+        block: usize,
         pc: i32,
         base: &str,
         operand_location: u32,
-    ) -> Result<(i32, bool, u8), String> {
+    ) -> Result<(i32, bool, u8), Error> {
         let operand_location = operand_location as i32;
         let operand_obj_code = operand_location - pc;
 
         if operand_obj_code < -2048 || operand_obj_code > 2047 {
             if base == "" {
-                return Err(err::handler().e307());
+                return Err(err::error().e307().at(Field::Operand));
             }
-            let (base_address, need_alloc) =
-                self.symbol_table.get_location_or_create(base, location)?;
-            let base_address = base_address as i32;
-            if need_alloc {
-                return Ok((0, true, 0));
+            let base_address: i32;
+            match self.evaluate(base, location, block)? {
+                Some(value) => {
+                    base_address = value.value as i32;
+                }
+                None => {
+                    return Ok((0, true, 0));
+                }
             }
 
             let disp = operand_location - base_address;
             if disp > 4095 || disp < 0 {
-                return Err(err::handler().e308(base));
+                return Err(err::error().e308(base).at(Field::Operand));
             }
 
-            return Ok((disp, need_alloc, 4));
+            return Ok((disp, false, 4));
         }
         return Ok((operand_obj_code, false, 2));
     }
@@ -841,26 +1387,25 @@ impl Parser {
         original_operand: &str,
         instruction_format: u8,
         extension: bool,
-    ) -> Result<(u8, u64, String, bool, u8), String> {
-        let (ni, obj_code, finial_operand, undone, byte): (u8, u64, String, bool, u8);
+    ) -> Result<(u64, String, bool, u8, bool), Error> {
+        let (obj_code, finial_operand, undone, byte): (u64, String, bool, u8);
+        let mut absolute = false;
 
         match instruction_format {
             1 => {
                 if !original_operand.eq("") {
-                    return Err(err::handler().e210(mnemonic));
+                    return Err(err::error().e210(mnemonic).at(Field::Operand));
                 }
 
-                ni = 0;
-                obj_code = 0 << 8 + opcode as u32;
+                obj_code = opcode as u64;
                 finial_operand = String::new();
                 undone = false;
                 byte = instruction_format;
             }
             2 => {
                 if original_operand.eq("") {
-                    return Err(err::handler().e211(mnemonic));
+                    return Err(err::error().e211(mnemonic).at(Field::Mnemonic));
                 }
-                ni = 0;
                 finial_operand = String::from(original_operand);
                 undone = false;
                 byte = instruction_format;
@@ -876,12 +1421,13 @@ impl Parser {
                             )
                             .unwrap();
                         } else {
-                            return Err(err::handler().e212(operand[0]));
+                            let suggestion = self.nearest_register(operand[0]);
+                            return Err(err::error().e212(operand[0]).suggest(suggestion).at(Field::Operand));
                         }
                     }
                     2 => {
                         if operand[0] == "" || operand[1] == "" {
-                            return Err(err::handler().e213());
+                            return Err(err::error().e213().at(Field::Operand));
                         }
                         if let Some(r1) = self.registers.get(operand[0]) {
                             if let Some(r2) = self.registers.get(operand[1]) {
@@ -891,32 +1437,33 @@ impl Parser {
                                 )
                                 .unwrap();
                             } else {
-                                return Err(err::handler().e212(operand[1]));
+                                let suggestion = self.nearest_register(operand[1]);
+                                return Err(err::error().e212(operand[1]).suggest(suggestion).at(Field::Operand));
                             }
                         } else {
-                            return Err(err::handler().e212(operand[0]));
+                            let suggestion = self.nearest_register(operand[0]);
+                            return Err(err::error().e212(operand[0]).suggest(suggestion).at(Field::Operand));
                         }
                     }
                     _ => {
-                        return Err(err::handler().e213());
+                        return Err(err::error().e213().at(Field::Operand));
                     }
                 }
             }
             34 => {
                 if mnemonic == "RSUB" {
                     if original_operand != "" {
-                        return Err(err::handler().e210(mnemonic));
+                        return Err(err::error().e210(mnemonic).at(Field::Operand));
                     }
 
-                    ni = AddressingMode::Simple as u8;
                     obj_code = (opcode as u64 + AddressingMode::Simple as u64) << 2 * 8;
                     finial_operand = String::from(original_operand);
                     undone = false;
                     byte = 3;
                 } else {
                     let operand: String;
+                    let ni: u8;
                     let mut xbpe: u8 = 0x0;
-                    let operand_location: u32;
 
                     if original_operand.bytes().nth(0).unwrap() == b'@' {
                         ni = AddressingMode::Indirect as u8;
@@ -934,24 +1481,15 @@ impl Parser {
                         if operand[1] == "X" {
                             xbpe += 8;
                         } else {
-                            return Err(err::handler().e214());
+                            return Err(err::error().e214().at(Field::Operand));
                         }
                     }
                     if operand.len() > 2 {
-                        return Err(err::handler().e214());
+                        return Err(err::error().e214().at(Field::Operand));
                     }
 
                     finial_operand = String::from(operand[0]);
 
-                    let mut num: i32 = -1;
-                    let mut is_digit = false;
-                    if ni == AddressingMode::Immediate as u8 {
-                        if let Ok(n) = i32::from_str_radix(operand[0], 10) {
-                            num = n;
-                            is_digit = true;
-                        }
-                    }
-
                     if extension {
                         xbpe += 1;
                         byte = 4;
@@ -959,82 +1497,378 @@ impl Parser {
                         byte = 3;
                     }
 
-                    if ni == AddressingMode::Immediate as u8 && is_digit {
-                        let operand = num;
-                        obj_code = self.fill_obj_code(opcode, ni, xbpe, operand, extension);
-                        undone = false;
-                    } else {
-                        if let Err(e) = self.symbol_legal(operand[0]) {
-                            return Err(format!("operand invalid: {}", e));
+                    if operand[0].starts_with('=') {
+                        if ni == AddressingMode::Immediate as u8 {
+                            return Err(err::error().e219(operand[0]).at(Field::Operand));
                         }
+                        self.literal_legal(operand[0], location)?;
+                    } else {
+                        self.expression_legal(operand[0])?;
+                    }
+                    let block = self.block;
+                    match self.evaluate(operand[0], location, block)? {
+                        Some(value) => {
+                            absolute = value.is_absolute();
+                            if value.is_external() && !extension {
+                                return Err(err::error().e222(operand[0]).at(Field::Operand));
+                            }
 
-                        let mut need_alloc: bool;
-                        (operand_location, need_alloc) = self
-                            .symbol_table
-                            .get_location_or_create(operand[0], location)?;
-
-                        if need_alloc || extension {
-                            let operand_obj_code = operand_location as i32;
-                            obj_code =
-                                self.fill_obj_code(opcode, ni, xbpe, operand_obj_code, extension);
-                        } else {
-                            let pc: i32;
-                            let (operand_obj_code, move_xbpe): (i32, u8);
-                            match location.checked_add(byte as u32) {
-                                Some(new_length) => {
-                                    pc = new_length as i32;
+                            if absolute || extension {
+                                // constant use direct addressing, format 4 use address
+                                if !extension && (value.value < -2048 || value.value > 4095) {
+                                    return Err(err::error().e215(value.value).at(Field::Operand));
                                 }
-                                None => {
-                                    self.symbol_table.remove_waiting(operand[0], location);
-                                    return Err(err::handler().e306());
+                                obj_code = self.fill_obj_code(
+                                    opcode,
+                                    ni,
+                                    xbpe,
+                                    value.value as i32,
+                                    extension,
+                                );
+                                undone = false;
+                            } else {
+                                let pc: u32;
+                                let (operand_obj_code, need_alloc, move_xbpe): (i32, bool, u8);
+                                match location.checked_add(byte as u32) {
+                                    Some(new_length) => {
+                                        pc = new_length;
+                                    }
+                                    None => {
+                                        return Err(err::error().e306().at(Field::Operand));
+                                    }
                                 }
-                            }
 
-                            (operand_obj_code, need_alloc, move_xbpe) = self.fill_operand(
-                                location,
-                                pc,
-                                self.base.clone().as_ref(),
-                                operand_location,
-                            )?;
-                            obj_code = self.fill_obj_code(
-                                opcode,
-                                ni,
-                                xbpe + move_xbpe,
-                                operand_obj_code,
-                                extension,
-                            );
+                                match self.block_address(block, pc) {
+                                    Some(address) => {
+                                        (operand_obj_code, need_alloc, move_xbpe) = self
+                                            .fill_operand(
+                                                location,
+                                                block,
+                                                address as i32,
+                                                self.base.clone().as_ref(),
+                                                value.value as u32,
+                                            )?;
+                                    }
+                                    None => {
+                                        // wait for the address of program block
+                                        (operand_obj_code, need_alloc, move_xbpe) = (0, true, 0);
+                                    }
+                                }
+                                obj_code = self.fill_obj_code(
+                                    opcode,
+                                    ni,
+                                    xbpe + move_xbpe,
+                                    operand_obj_code,
+                                    extension,
+                                );
+                                undone = need_alloc;
+                            }
+                        }
+                        None => {
+                            // forward reference, fill it in re_alloc
+                            obj_code = self.fill_obj_code(opcode, ni, xbpe, 0, extension);
+                            undone = true;
                         }
-
-                        undone = need_alloc;
                     }
                 }
             }
             _ => {
-                return Err(err::handler().e999(&format!(
+                return Err(err::error().e999(&format!(
                     "instruction_format error: found {} is {}",
                     mnemonic, instruction_format
                 )));
             }
         }
 
-        Ok((ni, obj_code, finial_operand, undone, byte))
+        Ok((obj_code, finial_operand, undone, byte, absolute))
+    }
+
+    // evaluate an expression, forward references are put into the waiting list
+    fn evaluate(
+        &mut self,
+        expression: &str,
+        location: u32,
+        block: usize,
+    ) -> Result<Option<Value>, Error> {
+        let value = self.resolve(expression, location, block)?;
+
+        if value.is_none() && expression.starts_with('=') {
+            self.literal_table.add_waiting(expression, location);
+        } else if value.is_none() {
+            for symbol in expression::symbols(expression) {
+                if self.lookup(&symbol).is_none() {
+                    self.symbol_table
+                        .get_location_or_create(&symbol, location)
+                        .map_err(|e| e.at(Field::Operand))?;
+                }
+            }
+        }
+
+        return Ok(value);
+    }
+
+    // evaluate an expression which can not have forward references
+    fn evaluate_defined(
+        &self,
+        expression: &str,
+        location: u32,
+        directive: &str,
+    ) -> Result<Value, Error> {
+        self.expression_legal(expression)?;
+
+        for symbol in expression::symbols(expression) {
+            if self.lookup(&symbol).is_none() {
+                let suggestion = self.symbol_table.nearest(&symbol);
+                return Err(err::error().e106(&symbol, directive).suggest(suggestion).at(Field::Operand));
+            }
+        }
+
+        match self.value_of(expression, location, self.block)? {
+            Some(value) => {
+                if value.is_external() {
+                    return Err(err::error().e222(expression).at(Field::Operand));
+                }
+                return Ok(value);
+            }
+            None => {
+                return Err(err::error().e999("expression has undefined symbol"));
+            }
+        }
+    }
+
+    fn expression_legal(&self, expression: &str) -> Result<(), Error> {
+        for symbol in expression::symbols(expression) {
+            if let Err(e) = self.symbol_legal(&symbol) {
+                return Err(e.at(Field::Operand));
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn remove_waiting(&mut self, expression: &str, location: u32) {
+        if expression.starts_with('=') {
+            self.literal_table.remove_waiting(expression, location);
+            return;
+        }
+        for symbol in expression::symbols(expression) {
+            self.symbol_table.remove_waiting(&symbol, location);
+        }
+    }
+
+    // move the location counter to the value of operand
+    fn org(&mut self, operand: &str, location: u32) -> Result<(), Error> {
+        let value = self.evaluate_defined(operand, location, "ORG")?;
+        let new_location: i64;
+        if value.is_absolute() {
+            new_location = self.absolute_value(&value, operand)?;
+        } else if value.block() == Some(self.block) {
+            new_location = value.value;
+        } else if self.block == 0 && self.address(&value).is_some() {
+            new_location = self.address(&value).unwrap();
+        } else {
+            return Err(err::error().e221(operand).at(Field::Operand));
+        }
+        if new_location < 0 || new_location > SizeLimit::Location as i64 {
+            return Err(err::error().e306().at(Field::Operand));
+        }
+
+        self.org_location = Some(location);
+        self.next_location = Some(new_location as u32);
+        log::print(
+            &format!("location counter move to 0x{:04X} ", new_location),
+            self.verbose,
+        );
+
+        return Ok(());
+    }
+
+    // switch to the program block, create it if it is a new one
+    fn use_block(&mut self, name: &str, location: u32) {
+        self.blocks[self.block].counter = location;
+
+        match self.blocks.iter().position(|b| b.name == name) {
+            Some(block) => {
+                self.block = block;
+            }
+            None => {
+                self.blocks.push(Block::new(name));
+                self.block = self.blocks.len() - 1;
+            }
+        }
+        self.next_location = Some(self.blocks[self.block].counter);
+        log::print(&format!("use program block {} ", self.block), self.verbose);
+    }
+
+    // assign the address of each program block at END,
+    // blocks are placed after the default block in order of first use
+    fn resolve_blocks(&mut self, location: u32) -> Result<(), Error> {
+        if location > self.blocks[self.block].length {
+            self.blocks[self.block].length = location;
+        }
+
+        let mut start: u32 = 0;
+        for i in 0..self.blocks.len() {
+            // locations of the default block are already addresses
+            if i != 0 {
+                self.blocks[i].start = start;
+            }
+            match self.blocks[i].start.checked_add(self.blocks[i].length) {
+                Some(end) => {
+                    start = end;
+                }
+                None => {
+                    return Err(err::error().e306());
+                }
+            }
+        }
+        self.blocks_resolved = true;
+
+        if start > self.program_length {
+            self.program_length = start;
+        }
+        if self.program_length > SizeLimit::Location as u32 {
+            return Err(err::error().e306());
+        }
+
+        return Ok(());
+    }
+
+    // add the literal into the literal pool if it is a new one
+    fn literal_legal(&mut self, literal: &str, location: u32) -> Result<(), Error> {
+        if self.literal_table.contains_key(literal) {
+            return Ok(());
+        }
+
+        let value = &literal[1..];
+        let (obj_code, byte): (u64, u32);
+        if value.starts_with("C'") || value.starts_with("X'") {
+            (obj_code, byte) = self.byte_obj_code(value)?;
+        } else if value.contains('\'') {
+            return Err(err::error().e219(literal).at(Field::Operand));
+        } else {
+            let result = self.evaluate_defined(value, location, literal)?;
+            if !result.is_absolute() {
+                return Err(err::error().e219(literal).at(Field::Operand));
+            }
+            let result = self.absolute_value(&result, value)?;
+            if result < -8388608 || result > 16777215 {
+                return Err(err::error().e219(literal).at(Field::Operand));
+            }
+            (obj_code, byte) = ((result as u64) & 0xFFFFFF, 3);
+        }
+        self.literal_table.insert(literal, obj_code, byte);
+
+        return Ok(());
+    }
+
+    // place all literals in the pool at location (LTORG, END),
+    // return the pool size and the codes waiting for them
+    fn flush_literals(
+        &mut self,
+        line_number: u32,
+        location: u32,
+    ) -> Result<(u32, Vec<u32>), Error> {
+        let mut offset: u32 = 0;
+        let mut need_modify_code: Vec<u32> = vec![];
+
+        for literal in self.literal_table.take_pool() {
+            let (obj_code, byte) = self.literal_table.get_value(&literal).unwrap();
+            let literal_location = location + offset;
+
+            need_modify_code.append(&mut self.literal_table.place(
+                &literal,
+                literal_location,
+                self.block,
+            ));
+            let mut code = Code::new(
+                line_number,
+                &format!("*\t{}", literal),
+                literal_location,
+                self.base.clone(),
+                literal.clone(),
+                obj_code,
+                byte,
+                true,
+                false,
+                false,
+                true,
+            );
+            code.block = self.block;
+            self.literal_codes.push(code);
+            log::print(
+                &format!("literal {} at 0x{:04X} ", literal, literal_location),
+                self.verbose,
+            );
+            offset += byte;
+        }
+
+        return Ok((offset, need_modify_code));
+    }
+
+    // object code and size of C'xxxx' or X'xxxx'
+    fn byte_obj_code(&self, operand: &str) -> Result<(u64, u32), Error> {
+        let mut tmp_obj_code = String::new();
+
+        if operand.len() <= 3 {
+            return Err(err::error().e206().at(Field::Operand));
+        }
+        let quote_s = operand.bytes().nth(1).unwrap();
+        let quote_e = operand.bytes().nth(operand.len() - 1).unwrap();
+        if quote_s != b'\'' || quote_e != b'\'' {
+            return Err(err::error().e206().at(Field::Operand));
+        }
+
+        match operand.bytes().nth(0).unwrap() {
+            b'C' => {
+                for i in 2..operand.len() - 1 {
+                    let letter = operand.chars().nth(i).unwrap();
+
+                    if !letter.is_ascii() {
+                        return Err(err::error().e207().at(Field::Operand));
+                    }
+
+                    tmp_obj_code.push_str(&format!("{:02X}", letter as u8));
+                }
+            }
+            b'X' => {
+                if (operand.len() - 3) % 2 != 0 {
+                    return Err(err::error().e209().at(Field::Operand));
+                }
+                for i in 2..operand.len() - 1 {
+                    tmp_obj_code.push(operand.chars().nth(i).unwrap());
+                }
+            }
+            _ => {
+                return Err(err::error().e206().at(Field::Operand));
+            }
+        }
+
+        if tmp_obj_code.len() > 16 {
+            return Err(err::error().e208().at(Field::Operand));
+        }
+
+        return Ok((
+            u64::from_str_radix(&tmp_obj_code, 16).unwrap(),
+            (tmp_obj_code.len() / 2) as u32,
+        ));
     }
 
-    fn symbol_legal(&self, label: &str) -> Result<(), String> {
+    fn symbol_legal(&self, label: &str) -> Result<(), Error> {
         if !self.symbol_table.is_legal(label) {
-            return Err(err::handler().e101(label));
+            return Err(err::error().e101(label));
         }
 
         if self.opcode_table.contains_key(label) {
-            return Err(err::handler().e103(label));
+            return Err(err::error().e103(label));
         }
 
         if self.registers.contains_key(label) {
-            return Err(err::handler().e104(label));
+            return Err(err::error().e104(label));
         }
 
         if self.reserve.contains_key(label) {
-            return Err(err::handler().e105(label));
+            return Err(err::error().e105(label));
         }
 
         return Ok(());
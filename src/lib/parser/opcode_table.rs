@@ -77,7 +77,21 @@ impl OpcodeTable {
         self.opcodes.get(mnemonic)
     }
 
+    // mnemonic and format of an opcode
+    pub fn find(&self, opcode: u8) -> Option<(&str, u8)> {
+        for (mnemonic, &(code, format)) in &self.opcodes {
+            if code == opcode {
+                return Some((mnemonic.as_str(), format));
+            }
+        }
+        None
+    }
+
     pub fn contains_key(&self, mnemonic: &str) -> bool {
         self.opcodes.contains_key(mnemonic)
     }
+
+    pub fn mnemonics(&self) -> impl Iterator<Item = &str> {
+        self.opcodes.keys().map(|mnemonic| mnemonic.as_str())
+    }
 }
@@ -0,0 +1,39 @@
+// edit distance of two words, ignoring case
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_ascii_uppercase().chars().collect();
+    let b: Vec<char> = b.to_ascii_uppercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 0..a.len() {
+        let mut current = vec![i + 1];
+        for j in 0..b.len() {
+            let replace = previous[j] + if a[i] == b[j] { 0 } else { 1 };
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    return previous[b.len()];
+}
+
+// the nearest candidate of a misspelled word, a third of the word can be wrong
+// and a short word must keep one letter, the first in alphabetical order if more
+// than one are as near, the word itself is not a candidate
+pub fn nearest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let length = word.chars().count();
+    let limit = (length / 3).max(1);
+    let mut best: Option<(usize, &str)> = None;
+
+    for candidate in candidates {
+        let d = distance(word, candidate);
+        if d > limit || d >= length || candidate == word {
+            continue;
+        }
+        best = match best {
+            Some((bd, b)) if bd < d || (bd == d && b <= candidate) => Some((bd, b)),
+            _ => Some((d, candidate)),
+        };
+    }
+
+    return best.map(|(_, candidate)| candidate.to_string());
+}
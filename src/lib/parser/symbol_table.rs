@@ -1,12 +1,16 @@
 use regex::Regex;
 use std::collections::HashMap;
 
-#[path = "../err.rs"]
-mod err;
+use super::err::{self, Error};
+use super::suggestion;
 
 pub struct SymbolData {
     location: u32,
     need_alloc: bool,
+    // program block of a relative symbol, None for absolute symbol
+    block: Option<usize>,
+    // imported by EXTREF, the address is filled by the linking loader
+    external: bool,
     waiting_list: Vec<u32>,
 }
 
@@ -19,13 +23,14 @@ impl SymbolTable {
     pub fn new() -> SymbolTable {
         SymbolTable {
             table: HashMap::new(),
-            legal_symbol_regex: Regex::new(r"^[A-Z][A-Z0-9]*$").unwrap(),
+            legal_symbol_regex: Regex::new(r"^\$?[A-Z][A-Z0-9]*$").unwrap(),
         }
     }
 
     pub fn remove_waiting(&mut self, symbol: &str, location: u32) {
-        let data = self.table.get_mut(symbol).unwrap();
-        data.waiting_list.retain(|&x| x != location);
+        if let Some(data) = self.table.get_mut(symbol) {
+            data.waiting_list.retain(|&x| x != location);
+        }
     }
 
     pub fn is_legal(&self, symbol: &str) -> bool {
@@ -41,13 +46,52 @@ impl SymbolTable {
         }
     }
 
+    // value and program block of a defined symbol
+    pub fn get_value(&self, symbol: &str) -> Option<(u32, Option<usize>)> {
+        match self.table.get(symbol) {
+            Some(data) if !data.need_alloc => Some((data.location, data.block)),
+            _ => None,
+        }
+    }
+
+    // all symbols sorted by name
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.table.keys().cloned().collect();
+        names.sort();
+        return names;
+    }
+
+    // defined or external symbol which is the nearest to a misspelled one
+    pub fn nearest(&self, symbol: &str) -> Option<String> {
+        let defined = self
+            .table
+            .iter()
+            .filter(|(name, data)| name.as_str() != symbol && (!data.need_alloc || data.external))
+            .map(|(name, _)| name.as_str());
+        return suggestion::nearest(symbol, defined);
+    }
+
+    pub fn is_external(&self, symbol: &str) -> bool {
+        match self.table.get(symbol) {
+            Some(data) => data.external,
+            None => false,
+        }
+    }
+
+    // define an external reference, used by EXTREF
+    pub fn insert_external(&mut self, symbol: &str) -> Result<Vec<u32>, Error> {
+        let waiting_list = self.insert_value(symbol, 0, None)?;
+        self.table.get_mut(symbol).unwrap().external = true;
+        return Ok(waiting_list);
+    }
+
     pub fn get_location_or_create(
         &mut self,
         symbol: &str,
         obj_code_location: u32,
-    ) -> Result<(u32, bool), String> {
+    ) -> Result<(u32, bool), Error> {
         if !self.is_legal(symbol) {
-            return Err(err::handler().e101(symbol));
+            return Err(err::error().e101(symbol));
         }
         match self.table.get_mut(symbol) {
             Some(data) => {
@@ -63,23 +107,39 @@ impl SymbolTable {
         }
     }
 
-    pub fn insert(&mut self, symbol: &str, obj_code_location: u32) -> Result<Vec<u32>, String> {
+    pub fn insert(
+        &mut self,
+        symbol: &str,
+        obj_code_location: u32,
+        block: usize,
+    ) -> Result<Vec<u32>, Error> {
+        return self.insert_value(symbol, obj_code_location, Some(block));
+    }
+
+    // define a symbol with an arbitrary value, used by EQU
+    pub fn insert_value(
+        &mut self,
+        symbol: &str,
+        value: u32,
+        block: Option<usize>,
+    ) -> Result<Vec<u32>, Error> {
         if !self.is_legal(symbol) {
-            return Err(err::handler().e101(symbol));
+            return Err(err::error().e101(symbol));
         }
 
         match self.table.get_mut(symbol) {
             Some(symbol_data) => {
                 if symbol_data.need_alloc {
                     symbol_data.need_alloc = false;
-                    symbol_data.location = obj_code_location;
+                    symbol_data.location = value;
+                    symbol_data.block = block;
                     return Ok(symbol_data.waiting_list.clone());
                 } else {
-                    return Err(err::handler().e102(symbol));
+                    return Err(err::error().e102(symbol));
                 }
             }
             None => {
-                self.have_location_insert(symbol, obj_code_location);
+                self.have_location_insert(symbol, value, block);
                 return Ok(vec![]);
             }
         }
@@ -87,7 +147,12 @@ impl SymbolTable {
 
     pub fn inter(&self) {
         self.table.iter().for_each(|(k, v)| {
-            println!("{} -> {:04X} : {:?}", k, v.location, v.waiting_list);
+            let kind = match v.block {
+                Some(block) => format!("R{}", block),
+                None if v.external => String::from("E"),
+                None => String::from("A"),
+            };
+            println!("{} -> {:04X} {} : {:?}", k, v.location, kind, v.waiting_list);
         });
     }
 
@@ -97,17 +162,21 @@ impl SymbolTable {
             SymbolData {
                 location: 0,
                 need_alloc: true,
+                block: None,
+                external: false,
                 waiting_list: vec![obj_code_location],
             },
         );
     }
 
-    fn have_location_insert(&mut self, symbol: &str, obj_code_location: u32) {
+    fn have_location_insert(&mut self, symbol: &str, obj_code_location: u32, block: Option<usize>) {
         self.table.insert(
             symbol.to_string(),
             SymbolData {
                 location: obj_code_location,
                 need_alloc: false,
+                block: block,
+                external: false,
                 waiting_list: vec![],
             },
         );
@@ -0,0 +1,629 @@
+use std::cmp::Ordering;
+use std::fs;
+
+use super::device::Devices;
+use super::err;
+use super::linker::{self, Image};
+use super::object::{self, ControlSection};
+use super::parser::opcode_table::OpcodeTable;
+use super::parser::SizeLimit;
+
+fn help_message(bin_path: &str) -> String {
+    let msg = format!("Usage: {} run <object path>...", bin_path);
+    let msg = format!("{}\n{}", msg, "use --progaddr <address> for hexadecimal load address, default is the start address");
+    let msg = format!("{}\n{}", msg, "use --memory <size> for hexadecimal memory size, default is 100000");
+    let msg = format!("{}\n{}", msg, "use --steps <count> to stop after count instructions");
+    let msg = format!("{}\n{}", msg, "use --device <device>=<file> to map a hexadecimal device to a file, - is stdin or stdout");
+    let msg = format!("{}\n{}", msg, "use --not-ready <count> for TD to report not ready count times after each RD or WD");
+
+    return msg;
+}
+
+// register numbers of format 2
+pub const A: usize = 0;
+pub const X: usize = 1;
+pub const L: usize = 2;
+pub const B: usize = 3;
+pub const S: usize = 4;
+pub const T: usize = 5;
+pub const PC: usize = 8;
+pub const SW: usize = 9;
+
+pub const REGISTERS: [(&str, usize); 8] = [
+    ("A", A),
+    ("X", X),
+    ("L", L),
+    ("B", B),
+    ("S", S),
+    ("T", T),
+    ("PC", PC),
+    ("SW", SW),
+];
+
+// L of the loaded program, returning to it stops the program
+const RETURN_ADDRESS: u32 = SizeLimit::Location as u32;
+
+// operand of format 3/4 after n and i bits
+enum Operand {
+    Immediate(u32),
+    Address(u32),
+}
+
+// SIC/XE CPU and memory
+pub struct Machine {
+    pub memory: Vec<u8>,
+    // 24 bits registers by register number, F is kept as f64
+    pub registers: [u32; 10],
+    pub f: f64,
+    pub halted: bool,
+    pub steps: u64,
+    pub devices: Devices,
+    opcode_table: OpcodeTable,
+}
+
+impl Machine {
+    pub fn new(image: &Image, memory_size: u32) -> Result<Machine, String> {
+        if image.start as usize + image.memory.len() > memory_size as usize {
+            return Err(err::handler().e605(memory_size));
+        }
+
+        let mut memory = vec![0; memory_size as usize];
+        for (i, byte) in image.memory.iter().enumerate() {
+            memory[image.start as usize + i] = byte.unwrap_or(0);
+        }
+        let mut registers = [0; 10];
+        registers[L] = RETURN_ADDRESS;
+        registers[PC] = image.execution;
+
+        Ok(Machine {
+            memory,
+            registers,
+            f: 0.0,
+            halted: false,
+            steps: 0,
+            devices: Devices::new(&[], 0),
+            opcode_table: OpcodeTable::new(),
+        })
+    }
+
+    pub fn byte(&self, address: u32) -> Result<u8, String> {
+        match self.memory.get(address as usize) {
+            Some(&byte) => return Ok(byte),
+            None => return Err(err::handler().e602(address)),
+        }
+    }
+
+    pub fn word(&self, address: u32) -> Result<u32, String> {
+        let mut word = 0;
+        for i in 0..3 {
+            word = (word << 8) | self.byte(address + i)? as u32;
+        }
+        return Ok(word);
+    }
+
+    fn set_byte(&mut self, address: u32, byte: u8) -> Result<(), String> {
+        match self.memory.get_mut(address as usize) {
+            Some(memory) => *memory = byte,
+            None => return Err(err::handler().e602(address)),
+        }
+        return Ok(());
+    }
+
+    fn set_word(&mut self, address: u32, word: u32) -> Result<(), String> {
+        for i in 0..3 {
+            self.set_byte(address + i, (word >> (8 * (2 - i))) as u8)?;
+        }
+        return Ok(());
+    }
+
+    fn float(&self, address: u32) -> Result<f64, String> {
+        let mut bits: u64 = 0;
+        for i in 0..6 {
+            bits = (bits << 8) | self.byte(address + i)? as u64;
+        }
+        return Ok(float_from_bits(bits));
+    }
+
+    fn set_float(&mut self, address: u32, value: f64) -> Result<(), String> {
+        let bits = float_to_bits(value);
+        for i in 0..6 {
+            self.set_byte(address + i, (bits >> (8 * (5 - i))) as u8)?;
+        }
+        return Ok(());
+    }
+
+    // condition code of SW, 00 is <, 01 is = and 10 is >
+    pub fn condition_code(&self) -> Ordering {
+        match (self.registers[SW] >> 6) & 0x3 {
+            0 => return Ordering::Less,
+            1 => return Ordering::Equal,
+            _ => return Ordering::Greater,
+        }
+    }
+
+    fn set_condition_code(&mut self, ordering: Ordering) {
+        let cc = match ordering {
+            Ordering::Less => 0,
+            Ordering::Equal => 1,
+            Ordering::Greater => 2,
+        };
+        self.registers[SW] = (self.registers[SW] & !0xc0) | (cc << 6);
+    }
+
+    // execute the instruction at PC
+    pub fn step(&mut self) -> Result<(), String> {
+        let pc = self.registers[PC];
+        let opcode = self.byte(pc)?;
+        let (mnemonic, format) = match self.opcode_table.find(opcode & 0xfc) {
+            Some((mnemonic, format)) => (mnemonic.to_string(), format),
+            None => return Err(err::handler().e601(opcode, pc)),
+        };
+
+        match format {
+            1 => {
+                if opcode & 0x3 != 0 {
+                    return Err(err::handler().e601(opcode, pc));
+                }
+                self.registers[PC] = pc + 1;
+                self.format_1(&mnemonic);
+            }
+            2 => {
+                if opcode & 0x3 != 0 {
+                    return Err(err::handler().e601(opcode, pc));
+                }
+                let registers = self.byte(pc + 1)?;
+                self.registers[PC] = pc + 2;
+                self.format_2(&mnemonic, pc, (registers >> 4) as usize, (registers & 0xf) as usize)?;
+            }
+            _ => {
+                let (operand, length) = self.target_address(pc, opcode)?;
+                self.registers[PC] = pc + length;
+                self.format_3(&mnemonic, pc, operand)?;
+            }
+        }
+
+        self.steps += 1;
+        if self.registers[PC] == RETURN_ADDRESS || self.registers[PC] == pc {
+            self.halted = true;
+        }
+        return Ok(());
+    }
+
+    // operand and length of format 3/4 and SIC instructions
+    fn target_address(&self, pc: u32, opcode: u8) -> Result<(Operand, u32), String> {
+        let ni = opcode & 0x3;
+        let second = self.byte(pc + 1)? as u32;
+        let third = self.byte(pc + 2)? as u32;
+        let indexed = second & 0x80 != 0;
+
+        let (mut address, length) = if ni == 0 {
+            // SIC instruction, 15 bits address
+            (((second & 0x7f) << 8) | third, 3)
+        } else if second & 0x10 != 0 {
+            // format 4, 20 bits address
+            let fourth = self.byte(pc + 3)? as u32;
+            (((second & 0xf) << 16) | (third << 8) | fourth, 4)
+        } else {
+            let displacement = ((second & 0xf) << 8) | third;
+            if second & 0x40 != 0 {
+                (self.registers[B] + displacement, 3)
+            } else if second & 0x20 != 0 {
+                // signed displacement from the next instruction
+                let displacement = ((displacement << 20) as i32 >> 20) as u32;
+                ((pc + 3).wrapping_add(displacement), 3)
+            } else {
+                (displacement, 3)
+            }
+        };
+        if indexed {
+            address += self.registers[X];
+        }
+        address &= 0xfffff;
+
+        match ni {
+            0b01 => return Ok((Operand::Immediate(address), length)),
+            0b10 => return Ok((Operand::Address(self.word(address)? & 0xfffff), length)),
+            _ => return Ok((Operand::Address(address), length)),
+        }
+    }
+
+    fn value(&self, operand: &Operand) -> Result<u32, String> {
+        match operand {
+            Operand::Immediate(value) => return Ok(*value),
+            Operand::Address(address) => return self.word(*address),
+        }
+    }
+
+    fn register(&self, number: usize, pc: u32) -> Result<u32, String> {
+        match number {
+            A | X | L | B | S | T | PC | SW => return Ok(self.registers[number]),
+            _ => return Err(err::handler().e601(self.byte(pc)?, pc)),
+        }
+    }
+
+    fn format_1(&mut self, mnemonic: &str) {
+        match mnemonic {
+            "FIX" => self.registers[A] = (self.f.trunc() as i64 as u32) & 0xffffff,
+            "FLOAT" => self.f = signed(self.registers[A]) as f64,
+            // F is always normalized, I/O channels are not simulated
+            _ => {}
+        }
+    }
+
+    fn format_2(&mut self, mnemonic: &str, pc: u32, r1: usize, r2: usize) -> Result<(), String> {
+        match mnemonic {
+            "CLEAR" => {
+                self.register(r1, pc)?;
+                self.registers[r1] = 0;
+            }
+            "RMO" => {
+                self.register(r2, pc)?;
+                self.registers[r2] = self.register(r1, pc)?;
+            }
+            "ADDR" | "SUBR" | "MULR" | "DIVR" => {
+                let (v1, v2) = (signed(self.register(r1, pc)?), signed(self.register(r2, pc)?));
+                let result = match mnemonic {
+                    "ADDR" => v2.wrapping_add(v1),
+                    "SUBR" => v2.wrapping_sub(v1),
+                    "MULR" => v2.wrapping_mul(v1),
+                    _ => {
+                        if v1 == 0 {
+                            return Err(err::handler().e603(pc));
+                        }
+                        v2 / v1
+                    }
+                };
+                self.registers[r2] = result as u32 & 0xffffff;
+            }
+            "COMPR" => {
+                let (v1, v2) = (signed(self.register(r1, pc)?), signed(self.register(r2, pc)?));
+                self.set_condition_code(v1.cmp(&v2));
+            }
+            "SHIFTL" | "SHIFTR" => {
+                // r2 is the count minus 1
+                let value = self.register(r1, pc)?;
+                let count = (r2 as u32 + 1) % 24;
+                self.registers[r1] = if mnemonic == "SHIFTL" {
+                    ((value << count) | (value >> (24 - count))) & 0xffffff
+                } else {
+                    ((signed(value) >> count) as u32) & 0xffffff
+                };
+            }
+            "TIXR" => {
+                self.registers[X] = (self.registers[X] + 1) & 0xffffff;
+                let value = signed(self.register(r1, pc)?);
+                self.set_condition_code(signed(self.registers[X]).cmp(&value));
+            }
+            // supervisor call is not simulated
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    fn format_3(&mut self, mnemonic: &str, pc: u32, operand: Operand) -> Result<(), String> {
+        // target address of store and jump instructions
+        let address = match operand {
+            Operand::Immediate(value) | Operand::Address(value) => value,
+        };
+        let store = mnemonic.starts_with("ST") && mnemonic != "STI";
+        if store {
+            if let Operand::Immediate(_) = operand {
+                return Err(err::handler().e601(self.byte(pc)?, pc));
+            }
+        }
+
+        match mnemonic {
+            "LDA" | "LDB" | "LDL" | "LDS" | "LDT" | "LDX" => {
+                let register = match mnemonic {
+                    "LDA" => A,
+                    "LDB" => B,
+                    "LDL" => L,
+                    "LDS" => S,
+                    "LDT" => T,
+                    _ => X,
+                };
+                self.registers[register] = self.value(&operand)?;
+            }
+            "LDCH" => {
+                let byte = match operand {
+                    Operand::Immediate(value) => value & 0xff,
+                    Operand::Address(address) => self.byte(address)? as u32,
+                };
+                self.registers[A] = (self.registers[A] & 0xffff00) | byte;
+            }
+            "STA" | "STB" | "STL" | "STS" | "STT" | "STX" | "STSW" => {
+                let register = match mnemonic {
+                    "STA" => A,
+                    "STB" => B,
+                    "STL" => L,
+                    "STS" => S,
+                    "STT" => T,
+                    "STX" => X,
+                    _ => SW,
+                };
+                self.set_word(address, self.registers[register])?;
+            }
+            "STCH" => self.set_byte(address, self.registers[A] as u8)?,
+            "ADD" | "SUB" | "MUL" | "DIV" | "AND" | "OR" => {
+                let (a, m) = (signed(self.registers[A]), signed(self.value(&operand)?));
+                let result = match mnemonic {
+                    "ADD" => a.wrapping_add(m),
+                    "SUB" => a.wrapping_sub(m),
+                    "MUL" => a.wrapping_mul(m),
+                    "AND" => a & m,
+                    "OR" => a | m,
+                    _ => {
+                        if m == 0 {
+                            return Err(err::handler().e603(pc));
+                        }
+                        a / m
+                    }
+                };
+                self.registers[A] = result as u32 & 0xffffff;
+            }
+            "COMP" => {
+                let m = signed(self.value(&operand)?);
+                self.set_condition_code(signed(self.registers[A]).cmp(&m));
+            }
+            "TIX" => {
+                self.registers[X] = (self.registers[X] + 1) & 0xffffff;
+                let m = signed(self.value(&operand)?);
+                self.set_condition_code(signed(self.registers[X]).cmp(&m));
+            }
+            "LDF" | "ADDF" | "SUBF" | "MULF" | "DIVF" | "COMPF" => {
+                let m = match operand {
+                    Operand::Immediate(_) => return Err(err::handler().e601(self.byte(pc)?, pc)),
+                    Operand::Address(address) => self.float(address)?,
+                };
+                match mnemonic {
+                    "LDF" => self.f = m,
+                    "ADDF" => self.f += m,
+                    "SUBF" => self.f -= m,
+                    "MULF" => self.f *= m,
+                    "DIVF" => {
+                        if m == 0.0 {
+                            return Err(err::handler().e603(pc));
+                        }
+                        self.f /= m;
+                    }
+                    _ => self.set_condition_code(self.f.partial_cmp(&m).unwrap_or(Ordering::Equal)),
+                }
+            }
+            "STF" => self.set_float(address, self.f)?,
+            "J" => self.registers[PC] = address,
+            "JEQ" | "JGT" | "JLT" => {
+                let ordering = match mnemonic {
+                    "JEQ" => Ordering::Equal,
+                    "JGT" => Ordering::Greater,
+                    _ => Ordering::Less,
+                };
+                if self.condition_code() == ordering {
+                    self.registers[PC] = address;
+                }
+            }
+            "JSUB" => {
+                self.registers[L] = self.registers[PC];
+                self.registers[PC] = address;
+            }
+            "RSUB" => self.registers[PC] = self.registers[L],
+            "TD" | "RD" | "WD" => {
+                let device = match operand {
+                    Operand::Immediate(value) => value as u8,
+                    Operand::Address(address) => self.byte(address)?,
+                };
+                match mnemonic {
+                    // < is ready, = is not ready
+                    "TD" => match self.devices.test(device) {
+                        true => self.set_condition_code(Ordering::Less),
+                        false => self.set_condition_code(Ordering::Equal),
+                    },
+                    "RD" => {
+                        let byte = self.devices.read(device)?;
+                        self.registers[A] = (self.registers[A] & 0xffff00) | byte as u32;
+                    }
+                    _ => self.devices.write(device, self.registers[A] as u8)?,
+                }
+            }
+            // protection key, processor status and interval timer are not simulated
+            _ => {}
+        }
+        return Ok(());
+    }
+}
+
+fn signed(value: u32) -> i32 {
+    return ((value << 8) as i32) >> 8;
+}
+
+// 1 bit sign, 11 bits exponent biased by 1024 and 36 bits fraction between 0.5 and 1
+fn float_from_bits(bits: u64) -> f64 {
+    let fraction = bits & 0xfffffffff;
+    if fraction == 0 {
+        return 0.0;
+    }
+    let exponent = ((bits >> 36) & 0x7ff) as i32 - 1024;
+    let value = fraction as f64 / (1u64 << 36) as f64 * 2f64.powi(exponent);
+    if bits & (1 << 47) != 0 {
+        return -value;
+    }
+    return value;
+}
+
+fn float_to_bits(value: f64) -> u64 {
+    if value == 0.0 || !value.is_finite() {
+        return 0;
+    }
+    let sign: u64 = if value < 0.0 { 1 << 47 } else { 0 };
+    let mut exponent = value.abs().log2().floor() as i32 + 1;
+    let mut fraction = (value.abs() / 2f64.powi(exponent) * (1u64 << 36) as f64).round() as u64;
+    if fraction >= 1 << 36 {
+        fraction >>= 1;
+        exponent += 1;
+    }
+    while fraction != 0 && fraction < 1 << 35 {
+        fraction <<= 1;
+        exponent -= 1;
+    }
+    let exponent = (exponent + 1024).clamp(0, 0x7ff) as u64;
+
+    return sign | (exponent << 36) | fraction;
+}
+
+// control sections of object files
+fn read(object_file_paths: &[String]) -> Result<Vec<ControlSection>, String> {
+    let mut sections: Vec<ControlSection> = Vec::new();
+    for path in object_file_paths {
+        match fs::read_to_string(path) {
+            Ok(contents) => sections.extend(object::parse(path, &contents)?),
+            Err(_) => return Err(err::handler().e002()),
+        }
+    }
+    return Ok(sections);
+}
+
+// options of run, also used by debug
+pub const OPTIONS: [&str; 5] = ["--progaddr", "--memory", "--steps", "--device", "--not-ready"];
+
+pub struct Simulator {
+    object_file_paths: Vec<String>,
+    progaddr: Option<u32>,
+    memory_size: u32,
+    steps: Option<u64>,
+    devices: Vec<(u8, String)>,
+    not_ready: u32,
+}
+
+impl Simulator {
+    // args[1] is run
+    pub fn new(args: &[String]) -> Result<Simulator, String> {
+        let mut simulator = Simulator::empty();
+
+        let mut i = 2;
+        while i < args.len() {
+            if OPTIONS.contains(&args[i].as_str()) {
+                if i + 1 >= args.len() {
+                    return Err(help_message(args[0].as_str()));
+                }
+                simulator.set_option(&args[i], &args[i + 1])?;
+                i += 1;
+            } else if args[i].starts_with('-') {
+                return Err(help_message(args[0].as_str()));
+            } else {
+                simulator.object_file_paths.push(args[i].clone());
+            }
+            i += 1;
+        }
+
+        if simulator.object_file_paths.len() == 0 {
+            return Err(help_message(args[0].as_str()));
+        }
+
+        Ok(simulator)
+    }
+
+    // simulator with default options and no object file
+    pub fn empty() -> Simulator {
+        Simulator {
+            object_file_paths: Vec::new(),
+            progaddr: None,
+            memory_size: SizeLimit::Location as u32 + 1,
+            steps: None,
+            devices: Vec::new(),
+            not_ready: 0,
+        }
+    }
+
+    // option is one of OPTIONS
+    pub fn set_option(&mut self, option: &str, value: &str) -> Result<(), String> {
+        if option == "--progaddr" {
+            match u32::from_str_radix(value, 16) {
+                Ok(address) if address <= SizeLimit::Location as u32 => self.progaddr = Some(address),
+                _ => return Err(err::handler().e505(value)),
+            }
+        } else if option == "--memory" {
+            match u32::from_str_radix(value, 16) {
+                Ok(size) if size > 0 && size <= SizeLimit::Location as u32 + 1 => self.memory_size = size,
+                _ => return Err(err::handler().e606(value, option)),
+            }
+        } else if option == "--steps" {
+            match value.parse::<u64>() {
+                Ok(count) => self.steps = Some(count),
+                Err(_) => return Err(err::handler().e606(value, option)),
+            }
+        } else if option == "--device" {
+            // device number and path, e.g. F1=input.txt or 05=-
+            let (number, path) = match value.split_once('=') {
+                Some((number, path)) if path.len() > 0 => (number, path),
+                _ => return Err(err::handler().e606(value, option)),
+            };
+            match u8::from_str_radix(number, 16) {
+                Ok(number) => {
+                    self.devices.retain(|(n, _)| *n != number);
+                    self.devices.push((number, path.to_string()));
+                }
+                Err(_) => return Err(err::handler().e606(value, option)),
+            }
+        } else {
+            match value.parse::<u32>() {
+                Ok(count) => self.not_ready = count,
+                Err(_) => return Err(err::handler().e606(value, option)),
+            }
+        }
+
+        return Ok(());
+    }
+
+    // link and load control sections, the first one is loaded at progaddr
+    // or its own start address
+    pub fn load(&self, sections: &[ControlSection]) -> Result<(Machine, Image), String> {
+        let progaddr = self.progaddr.unwrap_or(sections[0].start);
+        let image = linker::link(sections, progaddr)?;
+        let mut machine = Machine::new(&image, self.memory_size)?;
+        machine.devices = Devices::new(&self.devices, self.not_ready);
+
+        return Ok((machine, image));
+    }
+
+    // execute an instruction within the step limit
+    pub fn step(&self, machine: &mut Machine) -> Result<(), String> {
+        if let Some(steps) = self.steps {
+            if machine.steps >= steps {
+                return Err(err::handler().e604(steps));
+            }
+        }
+        return machine.step();
+    }
+
+    pub fn run(&self) -> Result<(), String> {
+        let (mut machine, _) = self.load(&read(&self.object_file_paths)?)?;
+
+        // output files are flushed even if the program fails
+        let result = self.execute(&mut machine);
+        machine.devices.flush()?;
+        result?;
+
+        println!("\nHalted after {} steps", machine.steps);
+        println!("{}", registers(&machine));
+
+        return Ok(());
+    }
+
+    fn execute(&self, machine: &mut Machine) -> Result<(), String> {
+        while !machine.halted {
+            self.step(machine)?;
+        }
+
+        return Ok(());
+    }
+}
+
+// registers in hexadecimal and F
+pub fn registers(machine: &Machine) -> String {
+    let mut registers: Vec<String> = REGISTERS
+        .iter()
+        .map(|(name, number)| format!("{}={:06X}", name, machine.registers[*number]))
+        .collect();
+    registers.push(format!("F={}", machine.f));
+
+    return registers.join(" ");
+}
@@ -0,0 +1,120 @@
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::err::{self, Error};
+use super::Line;
+
+// read source code files, INCLUDE is replaced by the lines of the included file
+pub struct Source {
+    include_dirs: Vec<String>,
+    // canonical path and path of the files being read, to find files including each other
+    stack: Vec<(PathBuf, String)>,
+    comma_regex: Regex,
+    c_re: Regex,
+    x_re: Regex,
+    include_regex: Regex,
+}
+
+impl Source {
+    pub fn new(include_dirs: &[String]) -> Source {
+        Source {
+            include_dirs: include_dirs.to_vec(),
+            stack: Vec::new(),
+            comma_regex: Regex::new(r"[ \t]*,[ \t]*").unwrap(),
+            c_re: Regex::new(r"[ \t]+C[ \t]*'").unwrap(),
+            x_re: Regex::new(r"[ \t]+X[ \t]*'").unwrap(),
+            include_regex: Regex::new(r"^INCLUDE[ \t]+(?:'([^']+)'|([^ \t]+))").unwrap(),
+        }
+    }
+
+    pub fn read(&mut self, path: &str) -> Result<Vec<Line>, Error> {
+        match fs::read_to_string(path) {
+            Ok(user_code) => return Ok(self.lines(path, &user_code)),
+            Err(_) => return Err(err::error().e002()),
+        }
+    }
+
+    // lines of source code read from path, or given by a caller without a file
+    pub fn lines(&mut self, path: &str, user_code: &str) -> Vec<Line> {
+        let canonical = fs::canonicalize(path).unwrap_or(PathBuf::from(path));
+        self.stack.push((canonical, path.to_string()));
+
+        let mut lines: Vec<Line> = Vec::new();
+        for (i, source_code) in user_code.lines().enumerate() {
+            let code = source_code.trim();
+            let code = self.comma_regex.replace_all(code, ",");
+            let code = self.c_re.replace_all(code.as_ref(), " C'");
+            let code = self.x_re.replace_all(code.as_ref(), " X'");
+
+            let mut line = Line {
+                file: path.to_string(),
+                line_number: (i + 1) as u32,
+                location: None,
+                code: code.to_string(),
+                source_code: source_code.to_string(),
+                expanded: false,
+                skipped: false,
+                error: None,
+            };
+
+            let name = match self.include_regex.captures(&line.code) {
+                Some(caps) => caps.get(1).or(caps.get(2)).unwrap().as_str().to_string(),
+                None => {
+                    lines.push(line);
+                    continue;
+                }
+            };
+            // INCLUDE is kept as a comment line before the included lines
+            line.code = String::new();
+            let included = match self.find(path, &name) {
+                Some(included) => included,
+                None => {
+                    line.error = Some(err::error().e411(&name));
+                    lines.push(line);
+                    continue;
+                }
+            };
+            let canonical = fs::canonicalize(&included).unwrap_or(PathBuf::from(&included));
+            if let Some(start) = self.stack.iter().position(|(file, _)| *file == canonical) {
+                let mut files: Vec<&str> = self.stack[start..].iter().map(|(_, path)| path.as_str()).collect();
+                files.push(&included);
+                line.error = Some(err::error().e412(&files.join(" -> ")));
+                lines.push(line);
+                continue;
+            }
+            match fs::read_to_string(&included) {
+                Ok(included_code) => {
+                    lines.push(line);
+                    let included_lines = self.lines(&included, &included_code);
+                    lines.extend(included_lines);
+                }
+                Err(_) => {
+                    line.error = Some(err::error().e411(&name));
+                    lines.push(line);
+                }
+            }
+        }
+
+        self.stack.pop();
+        return lines;
+    }
+
+    // the directory of the including file is searched first, then the -I directories
+    fn find(&self, path: &str, name: &str) -> Option<String> {
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        if let Some(parent) = Path::new(path).parent() {
+            dirs.push(parent.to_path_buf());
+        }
+        dirs.extend(self.include_dirs.iter().map(PathBuf::from));
+
+        for dir in dirs {
+            let file = dir.join(name);
+            if file.is_file() {
+                return Some(file.to_string_lossy().to_string());
+            }
+        }
+
+        return None;
+    }
+}
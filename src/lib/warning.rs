@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use super::err::{self, Code, Error, Field};
+use super::parser::Parser;
+use super::Line;
+
+// name of each warning in -W<name> and -Wno-<name>, and whether it is on by default
+pub const WARNINGS: [(&str, Code, bool); 5] = [
+    ("unused-label", Code::W001, false),
+    ("unreachable", Code::W002, true),
+    ("empty-reserve", Code::W003, true),
+    ("format4", Code::W004, false),
+    ("unused-base", Code::W005, true),
+];
+
+// warnings which are reported, -Werror makes them errors
+#[derive(Clone, Debug)]
+pub struct Warnings {
+    enabled: Vec<Code>,
+    pub error: bool,
+}
+
+impl Default for Warnings {
+    fn default() -> Warnings {
+        Warnings {
+            enabled: WARNINGS.iter().filter(|(_, _, on)| *on).map(|(_, code, _)| *code).collect(),
+            error: false,
+        }
+    }
+}
+
+impl Warnings {
+    // -W<name>, -Wno-<name> or -Werror, false if it isn't one of them
+    pub fn set(&mut self, option: &str) -> bool {
+        if option == "-Werror" {
+            self.error = true;
+            return true;
+        }
+        let (name, on) = match option.strip_prefix("-Wno-") {
+            Some(name) => (name, false),
+            None => match option.strip_prefix("-W") {
+                Some(name) => (name, true),
+                None => return false,
+            },
+        };
+        let code = match WARNINGS.iter().find(|(n, _, _)| *n == name) {
+            Some((_, code, _)) => *code,
+            None => return false,
+        };
+
+        self.enabled.retain(|c| *c != code);
+        if on {
+            self.enabled.push(code);
+        }
+        return true;
+    }
+
+    pub fn enabled(&self, code: Code) -> bool {
+        return self.enabled.contains(&code);
+    }
+}
+
+// labels which no operand refers to, except names of control sections
+pub fn unused_labels(lines: &[Line], parser: &Parser) -> Vec<(u32, Error)> {
+    // statement number of each label in a control section
+    let mut labels: Vec<(String, String, u32)> = Vec::new();
+    let mut references: HashMap<(String, String), ()> = HashMap::new();
+    let mut sections: Vec<String> = Vec::new();
+    let mut section = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.skipped || line.error.is_some() {
+            continue;
+        }
+        let (label, mnemonic, _) = parser.statement(&line.code);
+
+        if mnemonic == "START" || mnemonic == "CSECT" {
+            section = label.clone();
+            sections.push(label.clone());
+        } else if label != "" {
+            labels.push((label, section.clone(), (i + 1) as u32));
+        }
+        // the first instruction of END is in the first control section
+        let reference_section = match sections.first() {
+            Some(first) if mnemonic == "END" => first.clone(),
+            _ => section.clone(),
+        };
+        for symbol in parser.references(&line.code) {
+            references.insert((symbol, reference_section.clone()), ());
+        }
+    }
+
+    return labels
+        .into_iter()
+        .filter(|(label, section, _)| !references.contains_key(&(label.clone(), section.clone())))
+        .map(|(label, _, statement)| (statement, err::error().w001(&label).at(Field::Label)))
+        .collect();
+}
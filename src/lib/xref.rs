@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use super::parser::Parser;
+use super::Line;
+
+// definition line and reference lines of a symbol in a control section
+struct Entry {
+    defined: Option<String>,
+    references: Vec<String>,
+}
+
+// cross-reference report of symbols sorted by name, with unused and undefined flags
+pub fn report(lines: &[Line], parser: &Parser) -> String {
+    let mut entries: HashMap<(String, String), Entry> = HashMap::new();
+    let mut sections: Vec<String> = Vec::new();
+    let mut section = String::new();
+    // lines are file:line if there are included files
+    let included = lines.iter().any(|line| line.file != lines[0].file);
+
+    for line in lines {
+        let position = if included {
+            line.position()
+        } else {
+            line.line_number.to_string()
+        };
+        let (label, mnemonic, operand) = parser.statement(&line.code);
+        let mut defined: Vec<String> = Vec::new();
+
+        if mnemonic == "START" || mnemonic == "CSECT" {
+            section = label.clone();
+            sections.push(label.clone());
+        }
+        if label != "" {
+            defined.push(label);
+        }
+        if mnemonic == "EXTREF" {
+            defined.extend(operand.split(',').map(String::from));
+        }
+
+        for symbol in defined {
+            let entry = entries.entry((symbol, section.clone())).or_insert(Entry {
+                defined: None,
+                references: vec![],
+            });
+            if entry.defined.is_none() {
+                entry.defined = Some(position.clone());
+            }
+        }
+        // the first instruction of END is in the first control section
+        let reference_section = match sections.first() {
+            Some(first) if mnemonic == "END" => first.clone(),
+            _ => section.clone(),
+        };
+        for symbol in parser.references(&line.code) {
+            let entry = entries.entry((symbol, reference_section.clone())).or_insert(Entry {
+                defined: None,
+                references: vec![],
+            });
+            entry.references.push(position.clone());
+        }
+    }
+
+    let mut values: HashMap<(String, String), (Option<u32>, char)> = HashMap::new();
+    for (section, symbol, address, kind) in parser.symbol_list() {
+        values.insert((symbol, section), (address, kind));
+    }
+
+    let mut keys: Vec<&(String, String)> = entries.keys().collect();
+    keys.sort();
+
+    // file:line is wider than a line number
+    let width = entries
+        .values()
+        .filter_map(|entry| entry.defined.as_ref().map(|d| d.len()))
+        .fold(7, usize::max);
+    let mut contents = format!(
+        "Cross reference:\n{:<8} {:<8} {:<6} {:>width$}  {}\n",
+        "Symbol", "Section", "Value", "Defined", "References",
+        width = width
+    );
+    for key in keys {
+        let entry = &entries[key];
+        let (symbol, section) = key;
+        let (value, kind) = match values.get(key) {
+            Some(&(Some(address), kind)) => (format!("{:06X}", address), kind),
+            Some(&(None, kind)) => (String::from("------"), kind),
+            None => (String::from("------"), 'U'),
+        };
+        let defined = match &entry.defined {
+            Some(position) => position.clone(),
+            None => String::from("-"),
+        };
+
+        let mut flag = "";
+        if entry.defined.is_none() || kind == 'U' {
+            flag = "undefined";
+        } else if entry.references.len() == 0 && !sections.contains(symbol) {
+            flag = "unused";
+        }
+
+        let row = format!(
+            "{:<8} {:<8} {:<6} {:>width$}  {:<30} {}",
+            symbol,
+            section,
+            value,
+            defined,
+            entry.references.join(" "),
+            flag,
+            width = width
+        );
+        contents.push_str(&format!("{}\n", row.trim_end()));
+    }
+
+    return contents;
+}
@@ -1,12 +1,63 @@
 use std::env;
 use std::process;
 
-mod lib;
-use lib::Target;
+use assembler::{Debugger, Disassembler, Linker, Simulator, Target};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
+    if args.len() > 1 && args[1] == "link" {
+        let linker: Linker = Linker::new(&args).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+        if let Err(e) = linker.run() {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if args.len() > 1 && args[1] == "run" {
+        let simulator: Simulator = Simulator::new(&args).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+        if let Err(e) = simulator.run() {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if args.len() > 1 && args[1] == "debug" {
+        let debugger: Debugger = Debugger::new(&args).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+        if let Err(e) = debugger.run() {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if args.len() > 1 && args[1] == "disasm" {
+        let disassembler: Disassembler = Disassembler::new(&args).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+        if let Err(e) = disassembler.run() {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     let target: Target = Target::new(&args).unwrap_or_else(|err| {
         eprintln!("{}", err);
         process::exit(1);
@@ -0,0 +1,35 @@
+use std::fs;
+
+mod common;
+
+use common::{assembler, temp_dir};
+
+// LDA #1 at 100, 3 bytes of RESB and WORD 5 at 106
+const SOURCE: &str = "P START 100\nFIRST LDA #1\nBUF RESB 3\nW WORD 5\n END FIRST\n";
+
+// assemble SOURCE to a flat binary with the options
+fn binary(name: &str, args: &[&str]) -> Vec<u8> {
+    let dir = temp_dir(&format!("binary-{}", name));
+    fs::write(dir.join("prog.asm"), SOURCE).unwrap();
+
+    let output = assembler(&dir, &[&["--format", "bin", "-o", "prog"], args, &["prog.asm"]].concat());
+    assert!(output.status.success());
+    let bytes = fs::read(dir.join("prog.bin")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    return bytes;
+}
+
+#[test]
+fn reserved_bytes_are_zero() {
+    assert_eq!(binary("zero", &[]), vec![0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05]);
+}
+
+#[test]
+fn reserved_bytes_are_filled() {
+    let bytes = binary("fill", &["--fill", "AB"]);
+
+    // from the start address to the end of the program
+    assert_eq!(bytes.len(), 9);
+    assert_eq!(bytes, vec![0x01, 0x00, 0x01, 0xAB, 0xAB, 0xAB, 0x00, 0x00, 0x05]);
+}
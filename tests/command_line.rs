@@ -0,0 +1,161 @@
+use std::fs;
+
+mod common;
+
+use common::{assembler, records, temp_dir};
+
+#[test]
+fn errors_fail_with_any_number_of_files() {
+    let dir = temp_dir("cli-status");
+    fs::write(dir.join("good.asm"), "P START 0\n LDA #1\n END P\n").unwrap();
+    fs::write(dir.join("bad.asm"), "P START 0\n LDA NOPE\n END P\n").unwrap();
+
+    let one = assembler(&dir, &["bad.asm"]);
+    let many = assembler(&dir, &["good.asm", "bad.asm"]);
+    let good = assembler(&dir, &["good.asm"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(one.status.code(), Some(1));
+    assert_eq!(many.status.code(), Some(1));
+    assert!(good.status.success());
+}
+
+#[test]
+fn output_files_are_not_shared() {
+    let dir = temp_dir("cli-outputs");
+    for sub in ["a", "b"] {
+        fs::create_dir_all(dir.join(sub)).unwrap();
+        fs::write(dir.join(sub).join("x.asm"), "P START 0\n END P\n").unwrap();
+    }
+
+    let output = assembler(&dir, &["a/x.asm", "b/x.asm", "-d", "out"]);
+    let written = dir.join("out").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("E[008]"));
+    assert!(!written);
+}
+
+#[test]
+fn listing_starts_at_the_start_address() {
+    let dir = temp_dir("cli-listing");
+    fs::write(dir.join("prog.asm"), "P START 1000\nFIRST LDA #1\n END FIRST\n").unwrap();
+
+    let output = assembler(&dir, &["-l", "prog.lst", "prog.asm"]);
+    let listing = fs::read_to_string(dir.join("prog.lst")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    let locations: Vec<&str> = listing
+        .lines()
+        .skip(1)
+        .take(2)
+        .map(|line| line.split_whitespace().nth(1).unwrap())
+        .collect();
+    assert_eq!(locations, vec!["1000", "1000"]);
+}
+
+#[test]
+fn listing_locations_of_program_blocks_are_addresses() {
+    let dir = temp_dir("cli-blocks");
+    let source = "P START 0\nFIRST LDA #1\n USE DATA\nBUF RESW 1\n USE\n J FIRST\n USE DATA\n LTORG\n END FIRST\n";
+    fs::write(dir.join("prog.asm"), source).unwrap();
+
+    let output = assembler(&dir, &["-l", "prog.lst", "prog.asm"]);
+    let listing = fs::read_to_string(dir.join("prog.lst")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    // DATA is placed after the 6 bytes of the default block
+    let locations: Vec<&str> = listing
+        .lines()
+        .skip(1)
+        .take(9)
+        .map(|line| line.split_whitespace().nth(1).unwrap())
+        .collect();
+    assert_eq!(locations, vec!["0000", "0000", "0006", "0006", "0003", "0003", "0009", "0009", "0009"]);
+}
+
+#[test]
+fn xref_lists_definitions_and_references() {
+    let dir = temp_dir("cli-xref");
+    let source = "P START 0\nFIRST LDA BUF\n STA ZERO\n J FIRST\nBUF RESW 1\nSPARE RESW 1\n END FIRST\n";
+    fs::write(dir.join("prog.asm"), source).unwrap();
+
+    let output = assembler(&dir, &["--xref", "prog.asm"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    // the report is printed even if ZERO is undefined
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip_while(|line| *line != "Cross reference:")
+        .skip(2)
+        .take_while(|line| !line.is_empty())
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            vec!["BUF", "P", "000009", "5", "2"],
+            vec!["FIRST", "P", "000000", "2", "4", "7"],
+            vec!["P", "P", "000000", "1"],
+            vec!["SPARE", "P", "00000C", "6", "unused"],
+            vec!["ZERO", "P", "------", "-", "3", "undefined"],
+        ]
+    );
+}
+
+#[test]
+fn included_files_are_searched_in_include_dirs() {
+    let dir = temp_dir("cli-include");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(dir.join("main.asm"), "PROG START 0\n INCLUDE 'consts.asm'\nFIRST LDA ONE\n INCLUDE util.asm\n END FIRST\n").unwrap();
+    fs::write(dir.join("lib").join("consts.asm"), "MAXLEN EQU 4096\n").unwrap();
+    fs::write(dir.join("lib").join("util.asm"), " ADD TWO\n +LDT #MAXLEN\nONE WORD 1\nTWO WORD 2\n").unwrap();
+
+    let missing = assembler(&dir, &["-o", "missing", "main.asm"]);
+    let output = assembler(&dir, &["-I", "lib", "-o", "prog", "main.asm"]);
+    let object_program = fs::read_to_string(dir.join("prog.out")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(String::from_utf8_lossy(&missing.stdout).contains("E[411]"));
+    assert!(output.status.success());
+    assert_eq!(
+        records(&object_program),
+        vec!["H^PROG  000000000010", "T^000000^10^0320071B200775101000000001000002", "E^000000"]
+    );
+}
+
+#[test]
+fn files_including_each_other_are_errors() {
+    let dir = temp_dir("cli-cycle");
+    fs::write(dir.join("main.asm"), "PROG START 0\n INCLUDE 'loop1.asm'\n END PROG\n").unwrap();
+    fs::write(dir.join("loop1.asm"), " INCLUDE 'loop2.asm'\n").unwrap();
+    fs::write(dir.join("loop2.asm"), " INCLUDE 'loop1.asm'\n").unwrap();
+
+    let output = assembler(&dir, &["main.asm"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(stdout.contains("E[412]: Files include each other: loop1.asm -> loop2.asm -> loop1.asm"), "{}", stdout);
+}
+
+#[test]
+fn listing_lines_without_statements_are_aligned() {
+    let dir = temp_dir("cli-aligned");
+    fs::write(dir.join("prog.asm"), "P START 0\n. comment\nM MACRO\n RSUB\n MEND\nFIRST M\n END FIRST\n").unwrap();
+
+    let output = assembler(&dir, &["-l", "prog.lst", "prog.asm"]);
+    let listing = fs::read_to_string(dir.join("prog.lst")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    // the source code of comments and macro definitions starts at the label column
+    let rows: Vec<&str> = listing.lines().skip(2).take(2).collect();
+    assert_eq!(rows, vec!["    2        . comment", "    3        M MACRO"]);
+    assert_eq!(listing.lines().next().unwrap().find("Label"), Some(13));
+}
@@ -0,0 +1,152 @@
+// fixture of the tests, each test file uses a part of it
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// control sections with external references of Figure 2.15
+pub const CSECT: &str = concat!(
+    "COPY\tSTART\t0\n",
+    "\tEXTDEF\tBUFFER,BUFEND,LENGTH\n",
+    "\tEXTREF\tRDREC,WRREC\n",
+    "FIRST\tSTL\tRETADR\n",
+    "CLOOP\t+JSUB\tRDREC\n",
+    "\tLDA\tLENGTH\n",
+    "\tCOMP\t#0\n",
+    "\tJEQ\tENDFIL\n",
+    "\t+JSUB\tWRREC\n",
+    "\tJ\tCLOOP\n",
+    "ENDFIL\tLDA\t=C'EOF'\n",
+    "\tSTA\tBUFFER\n",
+    "\tLDA\t#3\n",
+    "\tSTA\tLENGTH\n",
+    "\t+JSUB\tWRREC\n",
+    "\tJ\t@RETADR\n",
+    "RETADR\tRESW\t1\n",
+    "LENGTH\tRESW\t1\n",
+    "\tLTORG\n",
+    "BUFFER\tRESB\t4096\n",
+    "BUFEND\tEQU\t*\n",
+    "MAXLEN\tEQU\tBUFEND-BUFFER\n",
+    "RDREC\tCSECT\n",
+    "\tEXTREF\tBUFFER,LENGTH,BUFEND\n",
+    "\tCLEAR\tX\n",
+    "\tCLEAR\tA\n",
+    "\tCLEAR\tS\n",
+    "\tLDT\tMAXLEN\n",
+    "RLOOP\tTD\tINPUT\n",
+    "\tJEQ\tRLOOP\n",
+    "\tRD\tINPUT\n",
+    "\tCOMPR\tA,S\n",
+    "\tJEQ\tEXIT\n",
+    "\t+STCH\tBUFFER,X\n",
+    "\tTIXR\tT\n",
+    "\tJLT\tRLOOP\n",
+    "EXIT\t+STX\tLENGTH\n",
+    "\tRSUB\n",
+    "INPUT\tBYTE\tX'F1'\n",
+    "MAXLEN\tWORD\tBUFEND-BUFFER\n",
+    "WRREC\tCSECT\n",
+    "\tEXTREF\tLENGTH,BUFFER\n",
+    "\tCLEAR\tX\n",
+    "\t+LDT\tLENGTH\n",
+    "WLOOP\tTD\t=X'05'\n",
+    "\tJEQ\tWLOOP\n",
+    "\t+LDCH\tBUFFER,X\n",
+    "\tWD\t=X'05'\n",
+    "\tTIXR\tT\n",
+    "\tJLT\tWLOOP\n",
+    "\tRSUB\n",
+    "\tEND\tFIRST\n",
+);
+
+// program blocks of Figure 2.10
+pub const BLOCKS: &str = concat!(
+    "COPY\tSTART\t0\n",
+    "FIRST\tSTL\tRETADR\n",
+    "CLOOP\tJSUB\tRDREC\n",
+    "\tLDA\tLENGTH\n",
+    "\tCOMP\t#0\n",
+    "\tJEQ\tENDFIL\n",
+    "\tJSUB\tWRREC\n",
+    "\tJ\tCLOOP\n",
+    "ENDFIL\tLDA\t=C'EOF'\n",
+    "\tSTA\tBUFFER\n",
+    "\tLDA\t#3\n",
+    "\tSTA\tLENGTH\n",
+    "\tJSUB\tWRREC\n",
+    "\tJ\t@RETADR\n",
+    "\tUSE\tCDATA\n",
+    "RETADR\tRESW\t1\n",
+    "LENGTH\tRESW\t1\n",
+    "\tUSE\tCBLKS\n",
+    "BUFFER\tRESB\t4096\n",
+    "BUFEND\tEQU\t*\n",
+    "MAXLEN\tEQU\tBUFEND-BUFFER\n",
+    "\tUSE\n",
+    "RDREC\tCLEAR\tX\n",
+    "\tCLEAR\tA\n",
+    "\tCLEAR\tS\n",
+    "\t+LDT\t#MAXLEN\n",
+    "RLOOP\tTD\tINPUT\n",
+    "\tJEQ\tRLOOP\n",
+    "\tRD\tINPUT\n",
+    "\tCOMPR\tA,S\n",
+    "\tJEQ\tEXIT\n",
+    "\tSTCH\tBUFFER,X\n",
+    "\tTIXR\tT\n",
+    "\tJLT\tRLOOP\n",
+    "EXIT\tSTX\tLENGTH\n",
+    "\tRSUB\n",
+    "\tUSE\tCDATA\n",
+    "INPUT\tBYTE\tX'F1'\n",
+    "\tUSE\n",
+    "WRREC\tCLEAR\tX\n",
+    "\tLDT\tLENGTH\n",
+    "WLOOP\tTD\t=X'05'\n",
+    "\tJEQ\tWLOOP\n",
+    "\tLDCH\tBUFFER,X\n",
+    "\tWD\t=X'05'\n",
+    "\tTIXR\tT\n",
+    "\tJLT\tWLOOP\n",
+    "\tRSUB\n",
+    "\tUSE\tCDATA\n",
+    "\tLTORG\n",
+    "\tUSE\tCBLKS\n",
+    "\tEND\tFIRST\n",
+);
+
+// empty directory of a test, the test removes it
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("assembler-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    return dir;
+}
+
+pub fn assembler(dir: &Path, args: &[&str]) -> Output {
+    return Command::new(env!("CARGO_BIN_EXE_assembler")).current_dir(dir).args(args).output().unwrap();
+}
+
+// the sample program of the repository
+pub fn sample() -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test-sicxe.txt");
+}
+
+// records of an object program, the length of a T record is checked and its codes are joined
+pub fn records(object_program: &str) -> Vec<String> {
+    let mut records: Vec<String> = Vec::new();
+    for record in object_program.lines() {
+        if !record.starts_with("T^") {
+            records.push(record.to_string());
+            continue;
+        }
+        let fields: Vec<&str> = record.trim_end_matches('^').split('^').collect();
+        let codes = fields[3..].concat();
+        assert_eq!(usize::from_str_radix(fields[2], 16).unwrap(), codes.len() / 2, "{}", record);
+        records.push(format!("T^{}^{:02X}^{}", fields[1], codes.len() / 2, codes));
+    }
+    return records;
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+mod common;
+
+use common::temp_dir;
+
+// debug the source code with the commands on stdin, return stdout
+fn debug(dir: &Path, source: &str, commands: &str) -> String {
+    fs::write(dir.join("prog.asm"), source).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_assembler"))
+        .current_dir(dir)
+        .args(["debug", "prog.asm"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(commands.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    return String::from_utf8_lossy(&output.stdout).to_string();
+}
+
+#[test]
+fn breakpoints_and_watchpoints_stop_the_program() {
+    let dir = temp_dir("debug-session");
+    let source = "P START 0\nFIRST LDA #5\n ADD #2\nSTORE STA RESULT\n RSUB\nRESULT RESW 1\n END FIRST\n";
+    let commands = "break STORE\ncontinue\nregisters\nwatch RESULT\ncontinue\nmemory RESULT 3\ncontinue\nquit\n";
+    let stdout = debug(&dir, source, commands);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let expected = concat!(
+        "P is loaded at 000000, type help for commands\n",
+        "000000 prog.asm:2:\tFIRST LDA #5\n",
+        "(debug) Breakpoint at 000006 STORE\n",
+        "(debug) Breakpoint at 000006 STORE\n",
+        "000006 prog.asm:4:\tSTORE STA RESULT\n",
+        "(debug) A=000007 X=000000 L=0FFFFF B=000000 S=000000 T=000000 PC=000006 SW=000000 F=0\n",
+        "(debug) Watchpoint at 00000C RESULT, 3 bytes\n",
+        "(debug) Watchpoint at 00000C RESULT: 000000 -> 000007\n",
+        "000009 prog.asm:5:\tRSUB\n",
+        "(debug) 00000C  000007\n",
+        "(debug) \n",
+        "Halted after 4 steps\n",
+        "0FFFFF no source code\n",
+        "(debug) ",
+    );
+    assert_eq!(stdout, expected);
+}
@@ -0,0 +1,169 @@
+use assembler::{assemble, Assembly, ErrorCode, Options, SymbolKind};
+
+mod common;
+
+use common::records;
+
+fn assembly(source: &str) -> Assembly {
+    match assemble(source, &Options::default()) {
+        Ok(assembly) => return assembly,
+        Err(diagnostics) => panic!("{:?}", diagnostics.0),
+    }
+}
+
+// value and kind of a symbol of the first control section
+fn symbol(assembly: &Assembly, name: &str) -> (Option<u32>, SymbolKind) {
+    let symbol = assembly.symbols.iter().find(|symbol| symbol.name == name).unwrap();
+    return (symbol.address, symbol.kind);
+}
+
+#[test]
+fn equ_defines_absolute_and_relative_symbols() {
+    let source = concat!(
+        "COPY START 0\n",
+        "FIRST LDA #MAXLEN\n",
+        " +LDT #MAXLEN\n",
+        " LDA BUFEND\n",
+        " COMP LIMIT\n",
+        " STA BUFFER\n",
+        "BUFFER RESB 100\n",
+        "BUFEND EQU *\n",
+        "MAXLEN EQU 4000\n",
+        "LIMIT EQU MAXLEN\n",
+        "ALIAS EQU BUFFER\n",
+        "LENGTH EQU BUFEND-BUFFER\n",
+        " LDA ALIAS\n",
+        " +JSUB ALIAS\n",
+        " J FIRST\n",
+        " END FIRST\n",
+    );
+    let assembly = assembly(source);
+
+    assert_eq!(symbol(&assembly, "BUFEND"), (Some(0x74), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "MAXLEN"), (Some(4000), SymbolKind::Absolute));
+    assert_eq!(symbol(&assembly, "LIMIT"), (Some(4000), SymbolKind::Absolute));
+    assert_eq!(symbol(&assembly, "ALIAS"), (Some(0x10), SymbolKind::Relative));
+    // the difference of two relative symbols is absolute
+    assert_eq!(symbol(&assembly, "LENGTH"), (Some(100), SymbolKind::Absolute));
+    // absolute symbols are not relocated, only +JSUB ALIAS is
+    assert_eq!(
+        records(&assembly.object_program),
+        vec![
+            "H^COPY  00000000007E",
+            "T^000000^10^010FA075100FA003206A2B0FA00F2000",
+            "T^000074^0A^032F994B1000103F2F82",
+            "M^000078^05",
+            "E^000000",
+        ]
+    );
+}
+
+#[test]
+fn literals_are_pooled_by_ltorg_and_end() {
+    let source = concat!(
+        "COPY START 0\n",
+        "FIRST LDA =C'EOF'\n",
+        " LDX =3\n",
+        " +LDA =X'05'\n",
+        " COMP =C'EOF'\n",
+        " LTORG\n",
+        "SECOND WD =X'05'\n",
+        " LDT =X'F1'\n",
+        " STA =C'EOF'\n",
+        " LDA =C'X',X\n",
+        " END FIRST\n",
+    );
+    let assembly = assembly(source);
+
+    // a literal is placed once, =C'EOF' after LTORG uses the one of the first pool
+    let literals: Vec<(String, u32)> = assembly
+        .listing
+        .iter()
+        .flat_map(|line| line.codes.iter())
+        .filter_map(|code| code.literal.clone().map(|literal| (literal, code.address)))
+        .collect();
+    let expected = [("=C'EOF'", 0x0D), ("=3", 0x10), ("=X'05'", 0x13), ("=X'F1'", 0x20), ("=C'X'", 0x21)];
+    assert_eq!(literals, expected.map(|(literal, address)| (String::from(literal), address)));
+    assert_eq!(
+        records(&assembly.object_program),
+        vec![
+            "H^COPY  000000000022",
+            "T^000000^1D^03200A07200A031000132B2000454F4600000305DF2FFC7720060F2FF0",
+            "T^00001D^05^03A001F158",
+            "M^000007^05",
+            "E^000000",
+        ]
+    );
+}
+
+#[test]
+fn org_moves_the_location_counter_and_back() {
+    let source = concat!(
+        "PROG START 1000\n",
+        "FIRST LDA VALUE\n",
+        " STA FLAGS\n",
+        " J FIRST\n",
+        "STAB RESB 30\n",
+        " ORG STAB\n",
+        "SYMBOL RESB 6\n",
+        "VALUE WORD 5\n",
+        "FLAGS RESB 2\n",
+        " ORG\n",
+        "TAIL WORD TAIL-STAB\n",
+        " ORG FIRST+3\n",
+        " LDX #1\n",
+        " ORG\n",
+        " END FIRST\n",
+    );
+    let assembly = assembly(source);
+
+    // the entries of STAB share its bytes, ORG without an operand goes back after STAB
+    assert_eq!(symbol(&assembly, "SYMBOL"), (Some(0x1009), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "VALUE"), (Some(0x100F), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "FLAGS"), (Some(0x1012), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "TAIL"), (Some(0x1027), SymbolKind::Relative));
+    // LDX #1 is written over STA FLAGS by a T record of its own
+    assert_eq!(
+        records(&assembly.object_program),
+        vec![
+            "H^PROG  00100000002A",
+            "T^001000^09^03200C0F200C3F2FF7",
+            "T^00100F^03^000005",
+            "T^001027^03^00001E",
+            "T^001003^03^050001",
+            "E^001000",
+        ]
+    );
+}
+
+#[test]
+fn forward_references_use_the_base_of_their_statement() {
+    let source = concat!(
+        "P START 0\n",
+        " BASE BUF\n",
+        "FIRST LDA BUF2\n",
+        " NOBASE\n",
+        " BASE BUF2-16\n",
+        " LDA BUF3\n",
+        "BUF RESB 4000\n",
+        "BUF2 RESB 10\n",
+        "BUF3 WORD 1\n",
+        " END FIRST\n",
+    );
+    let assembly = assembly(source);
+
+    // BUF2 is 0FA0 after BUF, BUF3 is 001A after BUF2-16
+    assert_eq!(
+        records(&assembly.object_program),
+        vec!["H^P     000000000FB3", "T^000000^06^034FA003401A", "T^000FB0^03^000001", "E^000000"]
+    );
+}
+
+#[test]
+fn nobase_ends_base_relative_addressing() {
+    let source = "P START 0\n BASE BUF\n NOBASE\nFIRST LDA BUF\nPAD RESB 4000\nBUF RESW 1\n END FIRST\n";
+    let diagnostics = assemble(source, &Options::default()).unwrap_err();
+    let codes: Vec<(ErrorCode, u32)> = diagnostics.0.iter().map(|diagnostic| (diagnostic.code, diagnostic.line)).collect();
+
+    assert_eq!(codes, vec![(ErrorCode::E307, 4), (ErrorCode::W005, 2)]);
+}
@@ -0,0 +1,47 @@
+use std::fs;
+
+mod common;
+
+use common::{assembler, temp_dir, BLOCKS, CSECT};
+
+// assemble the source code, disassemble the object program and assemble it again
+fn round_trip(name: &str, source: &str) {
+    let dir = temp_dir(&format!("disasm-{}", name));
+    fs::write(dir.join("prog.asm"), source).unwrap();
+
+    assert!(assembler(&dir, &["-o", "prog", "prog.asm"]).status.success());
+    assert!(assembler(&dir, &["disasm", "-o", "dis", "prog.out"]).status.success());
+    assert!(assembler(&dir, &["-o", "again", "dis.asm"]).status.success());
+    let object_program = fs::read(dir.join("prog.out")).unwrap();
+    let reassembled = fs::read(dir.join("again.out")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(object_program, reassembled);
+}
+
+#[test]
+fn control_sections_round_trip() {
+    round_trip("csect", CSECT);
+}
+
+#[test]
+fn program_blocks_round_trip() {
+    round_trip("blocks", BLOCKS);
+}
+
+#[test]
+fn modification_at_address_zero() {
+    let dir = temp_dir("disasm-zero");
+    // the word at address 0 is the address of the word after it
+    fs::write(dir.join("zero.out"), "H^P     000000000006\nT^000000^06^000003000005\nM^000000^06\nE^000000\n").unwrap();
+
+    let output = assembler(&dir, &["disasm", "-o", "zero", "zero.out"]);
+    let source = fs::read_to_string(dir.join("zero.asm")).unwrap_or_default();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        source,
+        ". disassembled from zero.out\nP\tSTART\t0\nL0000\tWORD\tL0003\nL0003\tBYTE\tX'000005'\n\tEND\tP\n"
+    );
+}
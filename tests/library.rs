@@ -0,0 +1,131 @@
+use std::fs;
+
+mod common;
+
+use assembler::{assemble, Argument, ErrorCode, Options, Severity, SymbolKind};
+
+#[test]
+fn library_is_same_as_command_line() {
+    let source = common::sample();
+    let dir = common::temp_dir("library");
+
+    let output = common::assembler(&dir, &["-o", "cli", source.to_str().unwrap()]);
+    assert!(output.status.success());
+    let object_program = fs::read_to_string(dir.join("cli.out")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let assembly = assemble(&fs::read_to_string(&source).unwrap(), &Options::default()).unwrap();
+    assert_eq!(assembly.object_program, object_program);
+
+    let length = assembly.symbols.iter().find(|symbol| symbol.name == "LENGTH").unwrap();
+    assert_eq!(length.kind, SymbolKind::Relative);
+    assert_eq!(length.address, Some(0x33));
+    assert!(assembly.listing.iter().any(|line| line.codes.len() > 0));
+}
+
+#[test]
+fn errors_are_diagnostics() {
+    let options = Options {
+        file_name: String::from("bad.asm"),
+        ..Options::default()
+    };
+    let diagnostics = assemble("P START 0\n LDA NOPE\n END P\n", &options).unwrap_err();
+
+    assert_eq!(diagnostics.0.len(), 1);
+    assert_eq!(diagnostics.0[0].file, "bad.asm");
+    assert_eq!(diagnostics.0[0].line, 2);
+    assert_eq!(diagnostics.0[0].code, ErrorCode::E311);
+    assert_eq!(diagnostics.0[0].severity, Severity::Error);
+    assert_eq!(diagnostics.0[0].arguments, vec![Argument::from("NOPE")]);
+    assert_eq!(diagnostics.0[0].span, Some((5, 9)));
+}
+
+#[test]
+fn warnings_are_not_errors() {
+    let source = "P START 0\nFIRST LDA #1\n RSUB\n LDA #2\nBUF RESB 0\n END FIRST\n";
+    let assembly = assemble(source, &Options::default()).unwrap();
+    let codes: Vec<ErrorCode> = assembly.warnings.iter().map(|warning| warning.code).collect();
+
+    assert_eq!(codes, vec![ErrorCode::W002, ErrorCode::W003]);
+    assert!(assembly.warnings.iter().all(|warning| warning.severity == Severity::Warning));
+
+    let mut options = Options::default();
+    assert!(options.warnings.set("-Wno-unreachable"));
+    assert!(options.warnings.set("-Werror"));
+    let diagnostics = assemble(source, &options).unwrap_err();
+    assert_eq!(diagnostics.0.len(), 1);
+    assert_eq!(diagnostics.0[0].code, ErrorCode::W003);
+}
+
+#[test]
+fn diagnostics_point_at_the_token() {
+    let options = Options {
+        file_name: String::from("bad.asm"),
+        ..Options::default()
+    };
+    let diagnostics = assemble("P START 0\nFIRST\tLDAA BUF\nBUF RESW 1\n END FIRST\n", &options).unwrap_err();
+
+    assert_eq!(diagnostics.0[0].code, ErrorCode::E201);
+    assert_eq!(diagnostics.0[0].suggestion, Some(String::from("LDA")));
+    assert_eq!(
+        diagnostics.0[0].render(false),
+        "bad.asm:2:7: error: E[201]: unknown mnemonic: LDAA\n2 | FIRST\tLDAA BUF\n  |      \t^^^^\n  = help: did you mean LDA?"
+    );
+}
+
+#[test]
+fn spans_are_in_the_field_of_the_error() {
+    let source = "P START 0\nX1 COMPR X1,A\nA1 RESW 1\nA1 WORD A1\n END P\n";
+    let diagnostics = assemble(source, &Options::default()).unwrap_err();
+    let spans: Vec<(ErrorCode, Option<(usize, usize)>)> = diagnostics
+        .0
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.span))
+        .collect();
+
+    // the operand X1, not the label X1, and the label A1, not the operand A1
+    assert_eq!(spans, vec![(ErrorCode::E212, Some((9, 11))), (ErrorCode::E102, Some((0, 2)))]);
+}
+
+#[test]
+fn suggestions_are_nearest_names() {
+    let source = "P START 0\nFIRST LDA BUFER\n CLEAR XX\n RESWW 1\nBUFFER RESW 1\n END FIRST\n";
+    let diagnostics = assemble(source, &Options::default()).unwrap_err();
+    let suggestions: Vec<(ErrorCode, Option<String>)> = diagnostics
+        .0
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.suggestion.clone()))
+        .collect();
+
+    assert!(suggestions.contains(&(ErrorCode::E212, Some(String::from("X")))));
+    assert!(suggestions.contains(&(ErrorCode::E201, Some(String::from("RESW")))));
+    assert!(suggestions.contains(&(ErrorCode::E311, Some(String::from("BUFFER")))));
+}
+
+#[test]
+fn suggestions_are_other_names() {
+    let source = "P START 0\n WORD 5\n N\n END P\n";
+    let diagnostics = assemble(source, &Options::default()).unwrap_err();
+    let suggestions: Vec<(ErrorCode, Option<String>)> = diagnostics
+        .0
+        .iter()
+        .map(|diagnostic| (diagnostic.code, diagnostic.suggestion.clone()))
+        .collect();
+
+    assert_eq!(suggestions, vec![(ErrorCode::E224, None), (ErrorCode::E201, None)]);
+}
+
+#[test]
+fn expression_errors_are_reported_once() {
+    for operand in ["BUF+BUF", "BUF*2", "2-BUF"] {
+        let source = format!("P START 0\n J FIRST\nFIRST LDA {}\nBUF RESW 1\n END FIRST\n", operand);
+        let diagnostics = assemble(&source, &Options::default()).unwrap_err();
+        let codes: Vec<ErrorCode> = diagnostics.0.iter().map(|diagnostic| diagnostic.code).collect();
+        assert_eq!(codes, vec![ErrorCode::E217]);
+    }
+
+    let source = "P START 0\nX WORD 99999999999*99999999999\nY WORD 9223372036854775807+1\n END P\n";
+    let diagnostics = assemble(source, &Options::default()).unwrap_err();
+    let codes: Vec<ErrorCode> = diagnostics.0.iter().map(|diagnostic| diagnostic.code).collect();
+    assert_eq!(codes, vec![ErrorCode::E216, ErrorCode::E216]);
+}
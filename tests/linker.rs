@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+mod common;
+
+use common::{assembler, temp_dir};
+
+// A defines X and refers to Y of B, the words are +Y and -X after linking
+const OBJECT_PROGRAM: &str = concat!(
+    "H^A     000000000006\n",
+    "D^X     ^000003\n",
+    "R^Y     \n",
+    "T^000000^06^000000000000\n",
+    "M^000000^06^+Y\n",
+    "M^000003^06^-X\n",
+    "E^000000\n",
+    "H^B     000000000003\n",
+    "D^Y     ^000001\n",
+    "T^000000^03^000010\n",
+    "E\n",
+);
+
+fn link(dir: &Path, args: &[&str]) -> Output {
+    return assembler(dir, &[&["link"], args].concat());
+}
+
+#[test]
+fn external_symbols_are_loaded_after_progaddr() {
+    let dir = temp_dir("link-estab");
+    fs::write(dir.join("prog.obj"), OBJECT_PROGRAM).unwrap();
+
+    let output = link(&dir, &["--progaddr", "1000", "-o", "linked", "prog.obj"]);
+    let load_map = String::from_utf8_lossy(&output.stdout).to_string();
+    let linked = fs::read_to_string(dir.join("linked.out")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    let rows: Vec<Vec<&str>> = load_map
+        .trim_end()
+        .lines()
+        .skip(1)
+        .map(|row| row.split_whitespace().collect())
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            vec!["A", "001000", "000006"],
+            vec!["X", "001003"],
+            vec!["B", "001006", "000003"],
+            vec!["Y", "001007"],
+        ]
+    );
+    assert_eq!(linked, "H^A     001000000009\nT^001000^09^001007FFEFFD000010\nE^001000");
+}
+
+#[test]
+fn records_out_of_section_are_errors() {
+    let dir = temp_dir("link-range");
+    fs::write(dir.join("text.obj"), "H^P     000000000003\nT^FFFFFFFF^01^00\nE^000000\n").unwrap();
+    fs::write(dir.join("modify.obj"), "H^P     000000000003\nT^000000^03^000000\nM^000002^06\nE\n").unwrap();
+
+    let text = link(&dir, &["-o", "text", "text.obj"]);
+    let modify = link(&dir, &["-o", "modify", "modify.obj"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(String::from_utf8_lossy(&text.stderr).contains("E[504]"));
+    assert!(String::from_utf8_lossy(&modify.stderr).contains("E[504]"));
+}
+
+#[test]
+fn input_is_not_overwritten() {
+    let dir = temp_dir("link-input");
+    fs::write(dir.join("a.out"), OBJECT_PROGRAM).unwrap();
+
+    let output = link(&dir, &["a.out"]);
+    let input = fs::read_to_string(dir.join("a.out")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("E[007]"));
+    assert_eq!(input, OBJECT_PROGRAM);
+}
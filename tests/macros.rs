@@ -0,0 +1,111 @@
+use assembler::{assemble, Assembly, Options};
+
+mod common;
+
+use common::records;
+
+fn assembly(source: &str) -> Assembly {
+    match assemble(source, &Options::default()) {
+        Ok(assembly) => return assembly,
+        Err(diagnostics) => panic!("{:?}", diagnostics.0),
+    }
+}
+
+fn address(assembly: &Assembly, name: &str) -> Option<u32> {
+    return assembly.symbols.iter().find(|symbol| symbol.name == name).and_then(|symbol| symbol.address);
+}
+
+#[test]
+fn macro_labels_are_unique_in_each_expansion() {
+    let source = concat!(
+        "COPY START 0\n",
+        "RDBUFF MACRO &INDEV,&BUFADR,&RECLTH,&EOR=04,&MAXLTH=4096\n",
+        " CLEAR X\n",
+        " +LDT #&MAXLTH\n",
+        "$LOOP TD =X'&INDEV'\n",
+        " JEQ $LOOP\n",
+        " RD =X'&INDEV'\n",
+        " COMP #&EOR\n",
+        " JEQ $EXIT\n",
+        " STCH &BUFADR,X\n",
+        " TIXR T\n",
+        " JLT $LOOP\n",
+        "$EXIT STX &RECLTH\n",
+        " MEND\n",
+        "FIRST RDBUFF F1,BUFFER,LENGTH\n",
+        " RDBUFF RECLTH=LENGTH,BUFADR=BUFFER,INDEV=F2,EOR=10\n",
+        " J FIRST\n",
+        " LTORG\n",
+        "LENGTH RESW 1\n",
+        "BUFFER RESB 4096\n",
+        " END FIRST\n",
+    );
+    let assembly = assembly(source);
+
+    // $ is replaced by $AA in the first expansion and $AB in the second
+    assert_eq!(address(&assembly, "$AALOOP"), Some(0x06));
+    assert_eq!(address(&assembly, "$AAEXIT"), Some(0x1D));
+    assert_eq!(address(&assembly, "$ABLOOP"), Some(0x26));
+    assert_eq!(address(&assembly, "$ABEXIT"), Some(0x3D));
+    assert_eq!(address(&assembly, "$LOOP"), None);
+    // keyword arguments in any order, EOR and MAXLTH have default values
+    assert_eq!(
+        records(&assembly.object_program),
+        vec![
+            "H^COPY  000000001048",
+            "T^000000^1D^B41075101000E3203A332FFADB203429000433200857A030B8503B2FE9",
+            "T^00001D^1D^132025B41075101000E3201B332FFADB201529000A33200857A010B850",
+            "T^00003A^0B^3B2FE91320053F2FBDF1F2",
+            "E^000000",
+        ]
+    );
+}
+
+#[test]
+fn conditional_assembly_selects_and_repeats_lines() {
+    let source = concat!(
+        "PROG START 0\n",
+        "&MODE SET 'DEBUG'\n",
+        "&N SET 3\n",
+        "TABLE MACRO &COUNT,&STEP=1\n",
+        "&CTR SET 1\n",
+        " WHILE (&CTR LE &COUNT)\n",
+        "ENT&CTR WORD &CTR*&STEP\n",
+        "&CTR SET &CTR+1\n",
+        " ENDW\n",
+        " MEND\n",
+        "FIRST LDA #0\n",
+        " IF (&MODE EQ 'DEBUG')\n",
+        " LDX #1\n",
+        " IF (&N GT 5 OR &N EQ 3)\n",
+        " LDS #3\n",
+        " ELSE\n",
+        " LDS #5\n",
+        " ENDIF\n",
+        " ELSE\n",
+        " LDX #2\n",
+        " ENDIF\n",
+        " IF (&MODE NE 'DEBUG' AND &N EQ 3)\n",
+        " LDT #9\n",
+        " ENDIF\n",
+        " TABLE &N,STEP=2\n",
+        " WHILE (&N GT 9)\n",
+        " WORD 0\n",
+        " ENDW\n",
+        " RSUB\n",
+        " END FIRST\n",
+    );
+    let assembly = assembly(source);
+
+    // the WHILE of TABLE defines a word for each of 1 to &N
+    assert_eq!(address(&assembly, "ENT1"), Some(0x09));
+    assert_eq!(address(&assembly, "ENT2"), Some(0x0C));
+    assert_eq!(address(&assembly, "ENT3"), Some(0x0F));
+    // the lines of false branches and of a WHILE which is never true are skipped
+    let skipped: Vec<u32> = assembly.listing.iter().filter(|line| line.skipped).map(|line| line.line).collect();
+    assert_eq!(skipped, vec![17, 20, 23, 27]);
+    assert_eq!(
+        records(&assembly.object_program),
+        vec!["H^PROG  000000000015", "T^000000^15^0100000500016D00030000020000040000064F0000", "E^000000"]
+    );
+}
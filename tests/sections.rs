@@ -0,0 +1,89 @@
+use assembler::{assemble, Assembly, Options, SymbolKind};
+
+mod common;
+
+use common::{records, BLOCKS, CSECT};
+
+fn assembly(source: &str) -> Assembly {
+    match assemble(source, &Options::default()) {
+        Ok(assembly) => return assembly,
+        Err(diagnostics) => panic!("{:?}", diagnostics.0),
+    }
+}
+
+// address and kind of a symbol in the control section
+fn symbol(assembly: &Assembly, section: &str, name: &str) -> (Option<u32>, SymbolKind) {
+    let symbol = assembly
+        .symbols
+        .iter()
+        .find(|symbol| symbol.section == section && symbol.name == name)
+        .unwrap();
+    return (symbol.address, symbol.kind);
+}
+
+#[test]
+fn program_blocks_are_placed_after_the_default_block() {
+    let assembly = assembly(BLOCKS);
+
+    // CDATA starts at 0066 and CBLKS at 0071, after the 0027 bytes of the default block
+    assert_eq!(symbol(&assembly, "COPY", "RETADR"), (Some(0x66), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "COPY", "INPUT"), (Some(0x6C), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "COPY", "BUFFER"), (Some(0x71), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "COPY", "BUFEND"), (Some(0x1071), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "COPY", "MAXLEN"), (Some(0x1000), SymbolKind::Absolute));
+    // the codes of Figure 2.12, written in order of address
+    assert_eq!(
+        records(&assembly.object_program),
+        vec![
+            "H^COPY  000000001071",
+            "T^000000^1B^1720634B20210320602900003320064B203B3F2FEE0320550F2056",
+            "T^00001B^1C^0100030F20484B20293E203FB410B400B44075101000E32038332FFA",
+            "T^000037^1B^DB2032A00433200857A02FB8503B2FEA13201F4F0000B410772017",
+            "T^000052^14^E3201B332FFA53A016DF2012B8503B2FEF4F0000",
+            "T^00006C^05^F1454F4605",
+            "E^000000",
+        ]
+    );
+}
+
+#[test]
+fn control_sections_refer_to_external_symbols() {
+    let assembly = assembly(CSECT);
+
+    // each control section starts at 0
+    assert_eq!(symbol(&assembly, "COPY", "LENGTH"), (Some(0x2D), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "RDREC", "MAXLEN"), (Some(0x28), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "WRREC", "WLOOP"), (Some(0x06), SymbolKind::Relative));
+    assert_eq!(symbol(&assembly, "RDREC", "BUFFER"), (None, SymbolKind::External));
+    // the object program of Figure 2.17
+    assert_eq!(
+        records(&assembly.object_program),
+        vec![
+            "H^COPY  000000001033",
+            "D^BUFFER^000033^BUFEND^001033^LENGTH^00002D",
+            "R^RDREC ^WRREC ",
+            "T^000000^1D^1720274B1000000320232900003320074B1000003F2FEC0320160F2016",
+            "T^00001D^0D^0100030F200A4B1000003E2000",
+            "T^000030^03^454F46",
+            "M^000004^05^+RDREC",
+            "M^000011^05^+WRREC",
+            "M^000024^05^+WRREC",
+            "E^000000",
+            "H^RDREC 00000000002B",
+            "R^BUFFER^LENGTH^BUFEND",
+            "T^000000^1D^B410B400B44077201FE3201B332FFADB2015A00433200957900000B850",
+            "T^00001D^0E^3B2FE9131000004F0000F1000000",
+            "M^000018^05^+BUFFER",
+            "M^000021^05^+LENGTH",
+            "M^000028^06^+BUFEND",
+            "M^000028^06^-BUFFER",
+            "E",
+            "H^WRREC 00000000001C",
+            "R^LENGTH^BUFFER",
+            "T^000000^1C^B41077100000E32012332FFA53900000DF2008B8503B2FEE4F000005",
+            "M^000003^05^+LENGTH",
+            "M^00000D^05^+BUFFER",
+            "E",
+        ]
+    );
+}
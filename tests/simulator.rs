@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+use common::{assembler, sample, temp_dir};
+
+// assemble the source code and run it, return stdout of run
+fn run(dir: &Path, source: &str, args: &[&str]) -> String {
+    fs::write(dir.join("prog.asm"), source).unwrap();
+    assert!(assembler(dir, &["-o", "prog", "prog.asm"]).status.success());
+
+    let output = assembler(dir, &[&["run", "prog.out"], args].concat());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    return String::from_utf8_lossy(&output.stdout).to_string();
+}
+
+#[test]
+fn float_round_trip() {
+    let dir = temp_dir("float");
+    // 7 is stored and loaded as a float, 7 * 7 is fixed again
+    let source = concat!(
+        "P START 0\n",
+        "FIRST LDA #7\n FLOAT\n STF TMP\n LDF TMP\n MULF TMP\n FIX\n RSUB\n",
+        "TMP RESB 6\n END FIRST\n",
+    );
+    let stdout = run(&dir, source, &[]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(stdout.contains("Halted after 7 steps"));
+    assert!(stdout.contains("A=000031 "));
+    assert!(stdout.contains("F=49"));
+}
+
+// the sample program copies F1 to 05 until the end of the input
+fn copy(name: &str, args: &[&str]) -> (String, Vec<u8>) {
+    let dir = temp_dir(&format!("run-{}", name));
+    let source = fs::read_to_string(sample()).unwrap();
+    fs::write(dir.join("input.txt"), "HELLO").unwrap();
+
+    let devices = ["--device", "F1=input.txt", "--device", "05=output.txt", "--steps", "100000"];
+    let stdout = run(&dir, &source, &[&devices[..], args].concat());
+    let output = fs::read(dir.join("output.txt")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    return (stdout, output);
+}
+
+#[test]
+fn sample_program_copies_devices() {
+    let (stdout, output) = copy("devices", &[]);
+
+    assert_eq!(output, b"HELLOEOF");
+    assert!(stdout.contains("Halted after 134 steps"));
+    assert!(stdout.contains("A=000046 X=000003 L=00002A B=000033 S=000000 T=000003 PC=0FFFFF SW=000040"));
+}
+
+#[test]
+fn devices_not_ready_are_polled() {
+    let (stdout, output) = copy("not-ready", &["--not-ready", "3"]);
+
+    // TD polls 3 more times before each byte is read or written
+    assert_eq!(output, b"HELLOEOF");
+    assert!(stdout.contains("Halted after 224 steps"));
+}
+
+#[test]
+fn file_is_not_read_and_written() {
+    let dir = temp_dir("same-file");
+    let source = "P START 0\nFIRST TD DEV\n RD DEV\n WD DEV\n RSUB\nDEV BYTE X'F1'\n END FIRST\n";
+    fs::write(dir.join("prog.asm"), source).unwrap();
+    fs::write(dir.join("io.txt"), "HI").unwrap();
+    assert!(assembler(&dir, &["-o", "prog", "prog.asm"]).status.success());
+
+    let output = assembler(&dir, &["run", "prog.out", "--device", "F1=io.txt"]);
+    let contents = fs::read_to_string(dir.join("io.txt")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("E[610]"));
+    assert_eq!(contents, "HI");
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::Path;
+
+mod common;
+
+use common::{assembler, sample, temp_dir};
+
+// assemble the source code in dir, return the object file
+fn assemble(dir: &Path, source: &Path, name: &str, two_pass: bool) -> Vec<u8> {
+    let mut args = vec!["-o", name, source.to_str().unwrap()];
+    if two_pass {
+        args.insert(0, "--two-pass");
+    }
+    let output = assembler(dir, &args);
+    assert!(output.status.success());
+
+    fs::read(dir.join(format!("{}.out", name))).unwrap()
+}
+
+#[test]
+fn two_pass_is_same_as_one_pass() {
+    let source = sample();
+    let dir = temp_dir("two-pass");
+
+    let one_pass = assemble(&dir, &source, "one_pass", false);
+    let two_pass = assemble(&dir, &source, "two_pass", true);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!one_pass.is_empty());
+    assert_eq!(one_pass, two_pass);
+}
+
+#[test]
+fn two_pass_reports_errors_of_pass_one() {
+    let dir = temp_dir("two-pass-errors");
+    // N is not defined before ORG, the passes disagree on the location of T2 because of it
+    let source = dir.join("org.asm");
+    fs::write(&source, "P START 0\nFIRST LDA #1\n ORG FIRST+N\nT2 WORD 8\nN EQU 30\n END FIRST\n").unwrap();
+
+    let output = assembler(&dir, &["--two-pass", "-o", "org", source.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let written = dir.join("org.out").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(stdout.contains("E[106]"), "{}", stdout);
+    assert!(!stdout.contains("E[314]"), "{}", stdout);
+    assert!(!written);
+}